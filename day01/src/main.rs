@@ -1,9 +1,9 @@
-use aoc2021::{config::Config, utils::get_input};
+use aoc2021::{config::Config, timing, utils::get_input};
 use day01::{part1, part2};
 
 use color_eyre::eyre::Result;
-use std::path::PathBuf;
 use structopt::StructOpt;
+use std::path::PathBuf;
 
 const DAY: u8 = 1;
 
@@ -20,13 +20,23 @@ struct RunArgs {
     /// run part 2
     #[structopt(long)]
     part2: bool,
+
+    /// never touch the network, fail if the input isn't cached
+    #[structopt(long)]
+    offline: bool,
+
+    /// print how long getting the input, parsing and each part took, and what
+    /// they allocated if built with the aoc2021/count-allocations feature
+    #[structopt(long)]
+    time: bool,
 }
 
 impl RunArgs {
     fn input(&self) -> Result<PathBuf> {
         match self.input {
             None => {
-                let config = Config::load()?;
+                let mut config = Config::load()?;
+                config.offline |= self.offline;
                 // this does nothing if the input file already exists, but
                 // simplifies the workflow after cloning the repo on a new computer
                 get_input(&config, DAY)?;
@@ -40,13 +50,20 @@ impl RunArgs {
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::from_args();
-    let input_path = args.input()?;
+    if args.time {
+        timing::enable();
+    }
+    let input_path = timing::phase("fetch input", || args.input())?;
 
     if !args.no_part1 {
-        part1(&input_path)?;
+        timing::phase("part 1", || part1(&input_path))?;
     }
     if args.part2 {
-        part2(&input_path)?;
+        timing::phase("part 2", || part2(&input_path))?;
+    }
+    if args.time {
+        // on stderr, so answers can still be piped
+        eprint!("{}", timing::report());
     }
     Ok(())
 }
@@ -1,4 +1,4 @@
-use aoc2021::parse;
+use aoc2021::{parse, timing};
 use std::path::Path;
 use thiserror::Error;
 
@@ -13,12 +13,15 @@ enum Command {
 pub fn part1(input: &Path) -> Result<(), Error> {
     use Command::*;
 
+    let commands: Vec<Command> = timing::phase("parse", || parse(input).map(Iterator::collect))?;
     let (depth, horizontal) =
-        parse::<Command>(input)?.fold((0u32, 0u32), |(depth, horizontal), command| match command {
-            Forward(amount) => (depth, horizontal + amount),
-            Up(amount) => (depth - amount, horizontal),
-            Down(amount) => (depth + amount, horizontal),
-        });
+        commands
+            .into_iter()
+            .fold((0u32, 0u32), |(depth, horizontal), command| match command {
+                Forward(amount) => (depth, horizontal + amount),
+                Up(amount) => (depth - amount, horizontal),
+                Down(amount) => (depth + amount, horizontal),
+            });
     println!("{}", depth * horizontal);
     Ok(())
 }
@@ -26,14 +29,18 @@ pub fn part1(input: &Path) -> Result<(), Error> {
 pub fn part2(input: &Path) -> Result<(), Error> {
     use Command::*;
 
+    let commands: Vec<Command> = timing::phase("parse", || parse(input).map(Iterator::collect))?;
     let (depth, horizontal, _) =
-        parse::<Command>(input)?.fold((0u32, 0u32, 0u32), |(depth, horizontal, aim), command| {
-            match command {
-                Forward(amount) => (depth + (aim * amount), horizontal + amount, aim),
-                Up(amount) => (depth, horizontal, aim - amount),
-                Down(amount) => (depth, horizontal, aim + amount),
-            }
-        });
+        commands
+            .into_iter()
+            .fold(
+                (0u32, 0u32, 0u32),
+                |(depth, horizontal, aim), command| match command {
+                    Forward(amount) => (depth + (aim * amount), horizontal + amount, aim),
+                    Up(amount) => (depth, horizontal, aim - amount),
+                    Down(amount) => (depth, horizontal, aim + amount),
+                },
+            );
     println!("{}", depth * horizontal);
     Ok(())
 }
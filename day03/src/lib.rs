@@ -1,4 +1,4 @@
-use aoc2021::parse;
+use aoc2021::{parse, timing};
 
 use std::{fmt, num::ParseIntError, path::Path, str::FromStr};
 use thiserror::Error;
@@ -12,12 +12,10 @@ struct Diagnostics {
 impl FromStr for Diagnostics {
     type Err = ParseIntError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        u16::from_str_radix(s.trim(), 2)
-            .map_err(Into::into)
-            .map(|value| Self {
-                value,
-                width: s.len(),
-            })
+        u16::from_str_radix(s.trim(), 2).map(|value| Self {
+            value,
+            width: s.len(),
+        })
     }
 }
 
@@ -80,22 +78,22 @@ impl FromIterator<Diagnostics> for DiagnosticsReport {
     where
         T: IntoIterator<Item = Diagnostics>,
     {
-        let mut counts = vec![0_usize; 16];
+        let mut counts = [0_usize; 16];
         let reports = iter.into_iter().collect::<Vec<_>>();
         let threshold = reports.len() / 2;
         let mut width = 0;
         for report in reports.iter() {
-            for i in 0..16 {
+            for (i, count) in counts.iter_mut().enumerate() {
                 if report.value & 1 << i != 0 {
-                    counts[i] += 1;
+                    *count += 1;
                 }
             }
             width = width.max(report.width);
         }
 
         let mut gamma: u16 = 0;
-        for pos in 0..counts.len() {
-            if counts[pos] > threshold {
+        for (pos, count) in counts.iter().enumerate() {
+            if *count > threshold {
                 gamma |= 1 << pos;
             }
         }
@@ -115,13 +113,13 @@ impl FromIterator<Diagnostics> for DiagnosticsReport {
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    let report: DiagnosticsReport = parse(input)?.collect();
+    let report: DiagnosticsReport = timing::phase("parse", || parse(input).map(Iterator::collect))?;
     println!("{}", report.power_consumption());
     Ok(())
 }
 
 pub fn part2(input: &Path) -> Result<(), Error> {
-    let report: DiagnosticsReport = parse(input)?.collect();
+    let report: DiagnosticsReport = timing::phase("parse", || parse(input).map(Iterator::collect))?;
     let generator_rating = report.get_rating(Rating::Oxygen)?;
     let scrubber_rating = report.get_rating(Rating::Scrubber)?;
     println!("{}", generator_rating * scrubber_rating);
@@ -1,4 +1,7 @@
-use aoc2021::input::{parse_newline, Separated};
+use aoc2021::{
+    input::{parse_newline, Separated},
+    timing,
+};
 
 use std::{fmt::Debug, num::ParseIntError, path::Path, str::FromStr};
 use thiserror::Error;
@@ -21,8 +24,8 @@ impl FromIterator<String> for BingoSubsystem {
                 .collect();
             let mut boards: Vec<Board> = Vec::new();
 
-            while let Some(block) = iter.next() {
-                let board = Board::from_str(&block.trim()).expect("failed to parse board");
+            for block in iter {
+                let board = Board::from_str(block.trim()).expect("failed to parse board");
                 boards.push(board);
             }
 
@@ -63,7 +66,7 @@ impl BingoSubsystem {
                     winning_boards.push((b.clone(), *number));
                     return false;
                 }
-                return true;
+                true
             });
         }
 
@@ -121,7 +124,7 @@ impl Board {
                 .numbers
                 .iter()
                 .filter(|m| !m.1)
-                .map(|m| m.0 as u32)
+                .map(|m| m.0)
                 .sum::<u32>();
         }
         sum
@@ -148,9 +151,7 @@ impl FromStr for BoardRow {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut numbers: Vec<BoardNumber> = Vec::new();
         for number in s.split_whitespace() {
-            let number = u32::from_str(number)
-                .map_err(Into::into)
-                .map(|number| (number, false))?;
+            let number = u32::from_str(number).map(|number| (number, false))?;
             numbers.push(number);
         }
         Ok(Self { numbers })
@@ -159,15 +160,24 @@ impl FromStr for BoardRow {
 
 type BoardNumber = (u32, bool);
 
+fn parse_bingo(input: &Path) -> Result<BingoSubsystem, Error> {
+    timing::phase("parse", || {
+        Ok(BingoSubsystem::from_iter(parse_newline::<String>(input)?))
+    })
+}
+
 pub fn part1(input: &Path) -> Result<(), Error> {
-    let mut bingo_system = BingoSubsystem::from_iter(parse_newline::<String>(input)?);
-    println!("{}", bingo_system.play()?);
+    let mut bingo_system = parse_bingo(input)?;
+    println!("{}", timing::phase("play", || bingo_system.play())?);
     Ok(())
 }
 
 pub fn part2(input: &Path) -> Result<(), Error> {
-    let mut bingo_system = BingoSubsystem::from_iter(parse_newline::<String>(input)?);
-    println!("{}", bingo_system.find_final_board()?);
+    let mut bingo_system = parse_bingo(input)?;
+    println!(
+        "{}",
+        timing::phase("play", || bingo_system.find_final_board())?
+    );
     Ok(())
 }
 
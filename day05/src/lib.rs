@@ -1,4 +1,4 @@
-use aoc2021::parse;
+use aoc2021::{parse, timing};
 
 use std::{collections::HashMap, hash::Hash, path::Path};
 use thiserror::Error;
@@ -80,18 +80,21 @@ struct Point {
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    let lines: Vec<Line> = parse::<Line>(input)?
-        .filter(|l| l.is_horizontal() || l.is_vertical())
-        .collect();
-    let map = Map::from_lines(lines);
-    println!("{}", map.find_overlaps());
+    let map = timing::phase("parse", || -> Result<_, Error> {
+        let lines: Vec<Line> = parse::<Line>(input)?
+            .filter(|l| l.is_horizontal() || l.is_vertical())
+            .collect();
+        Ok(Map::from_lines(lines))
+    })?;
+    println!("{}", timing::phase("overlaps", || map.find_overlaps()));
     Ok(())
 }
 
 pub fn part2(input: &Path) -> Result<(), Error> {
-    let lines: Vec<Line> = parse::<Line>(input)?.collect();
-    let map = Map::from_lines(lines);
-    println!("{}", map.find_overlaps());
+    let map = timing::phase("parse", || -> Result<_, Error> {
+        Ok(Map::from_lines(parse::<Line>(input)?.collect()))
+    })?;
+    println!("{}", timing::phase("overlaps", || map.find_overlaps()));
     Ok(())
 }
 
@@ -1,21 +1,21 @@
-use aoc2021::input::{parse_newline, Separated};
+use aoc2021::{
+    input::{parse_newline, Separated},
+    timing,
+};
 
 use std::{collections::HashMap, num::ParseIntError, path::Path, str::FromStr};
 use thiserror::Error;
 
 fn calculate_fish(data: &str, days: u32) -> Result<usize, Error> {
-    let school: HashMap<u8, usize> = <Separated<u8>>::from_str(data.trim())?.into_iter().fold(
-        HashMap::new(),
-        |mut school, v| {
-            *school.entry(v).or_insert(0) += 1;
-            school
-        },
-    );
+    let fish = timing::phase("parse", || <Separated<u8>>::from_str(data.trim()))?;
+    let school: HashMap<u8, usize> = fish.into_iter().fold(HashMap::new(), |mut school, v| {
+        *school.entry(v).or_insert(0) += 1;
+        school
+    });
 
     Ok((0..days)
         .fold(school, |school, _| iterate(&school))
-        .into_iter()
-        .map(|(_, v)| v)
+        .into_values()
         .sum::<usize>())
 }
 
@@ -33,14 +33,18 @@ fn iterate(school: &HashMap<u8, usize>) -> HashMap<u8, usize> {
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    let input = parse_newline::<String>(input)?.nth(0).unwrap();
+    let input = timing::phase("read", || {
+        parse_newline::<String>(input).map(|mut lines| lines.next().unwrap())
+    })?;
     let amount = calculate_fish(&input, 80)?;
     println!("{}", amount);
     Ok(())
 }
 
 pub fn part2(input: &Path) -> Result<(), Error> {
-    let input = parse_newline::<String>(input)?.nth(0).unwrap();
+    let input = timing::phase("read", || {
+        parse_newline::<String>(input).map(|mut lines| lines.next().unwrap())
+    })?;
     let amount = calculate_fish(&input, 256)?;
     println!("{}", amount);
     Ok(())
@@ -1,4 +1,7 @@
-use aoc2021::input::{parse_newline, Separated};
+use aoc2021::{
+    input::{parse_newline, Separated},
+    timing,
+};
 
 use std::{num::ParseIntError, path::Path, str::FromStr};
 use thiserror::Error;
@@ -15,7 +18,7 @@ fn calculate_fuel_part2(data: &str) -> Result<i32, Error> {
 }
 
 fn calculate_lowest_fuel(data: &str, fuel_calc: impl Fn(i32, i32) -> i32) -> Result<i32, Error> {
-    let crabs: Vec<i32> = <Separated<i32>>::from_str(data.trim())?
+    let crabs: Vec<i32> = timing::phase("parse", || <Separated<i32>>::from_str(data.trim()))?
         .into_iter()
         .collect();
     let min = crabs.iter().min().expect("no min");
@@ -33,14 +36,18 @@ fn calculate_lowest_fuel(data: &str, fuel_calc: impl Fn(i32, i32) -> i32) -> Res
 }
 
 pub fn part1(input: &Path) -> Result<(), Error> {
-    let input = parse_newline::<String>(input)?.nth(0).unwrap();
+    let input = timing::phase("read", || {
+        parse_newline::<String>(input).map(|mut lines| lines.next().unwrap())
+    })?;
     let amount = calculate_fuel(&input)?;
     println!("{}", amount);
     Ok(())
 }
 
 pub fn part2(input: &Path) -> Result<(), Error> {
-    let input = parse_newline::<String>(input)?.nth(0).unwrap();
+    let input = timing::phase("read", || {
+        parse_newline::<String>(input).map(|mut lines| lines.next().unwrap())
+    })?;
     let amount = calculate_fuel_part2(&input)?;
     println!("{}", amount);
     Ok(())
@@ -0,0 +1,178 @@
+//! Heap allocation counters, fed by [`CountingAllocator`].
+//!
+//! With the `count-allocations` feature the allocator is installed as the
+//! global allocator of every binary linking this crate, and the `--time`
+//! report of the day binaries shows what each phase allocated:
+//!
+//! ```sh
+//! cargo run --release -p day05 --features aoc2021/count-allocations -- --part2 --time
+//! ```
+//!
+//! Benchmarks can read the counters directly with [`stats`] or [`measure`].
+//! Without the feature nothing is counted unless a binary installs the
+//! allocator itself.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// The system allocator, counting what goes through it. Reallocations count
+/// as allocations of their new size.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CountingAllocator;
+
+fn allocated(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED.fetch_add(size, Ordering::Relaxed);
+    let live = LIVE.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(live, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+            allocated(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Whether the counting allocator is installed by this crate.
+pub const fn enabled() -> bool {
+    cfg!(feature = "count-allocations")
+}
+
+/// The counters since the program started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub allocations: usize,
+    /// Bytes allocated, freed or not
+    pub bytes: usize,
+    /// Bytes allocated and not yet freed
+    pub live: usize,
+}
+
+pub fn stats() -> Stats {
+    Stats {
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        bytes: ALLOCATED.load(Ordering::Relaxed),
+        live: LIVE.load(Ordering::Relaxed),
+    }
+}
+
+/// What was allocated between two points.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub allocations: usize,
+    pub bytes: usize,
+    /// The most bytes live at once in between
+    pub peak: usize,
+}
+
+/// Run `f`, returning what it allocated along with its result. Measurements
+/// nest: the peak seen by an outer one includes the peaks of inner ones.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Usage) {
+    let before = stats();
+    let outer_peak = PEAK.swap(before.live, Ordering::Relaxed);
+    let result = f();
+    let after = stats();
+    let usage = Usage {
+        allocations: after.allocations - before.allocations,
+        bytes: after.bytes - before.bytes,
+        peak: PEAK.fetch_max(outer_peak, Ordering::Relaxed),
+    };
+    (result, usage)
+}
+
+/// Byte counts in the largest binary unit under which they stay above one.
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1}{}", size, UNITS[unit])
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} allocations, {}, peak {}",
+            self.allocations,
+            format_bytes(self.bytes),
+            format_bytes(self.peak)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_measure() {
+        let allocator = CountingAllocator;
+        let layout = Layout::from_size_align(4096, 8).unwrap();
+        let ((), usage) = measure(|| unsafe {
+            let first = allocator.alloc(layout);
+            let ((), inner) = measure(|| {
+                let second = allocator.alloc_zeroed(layout);
+                allocator.dealloc(second, layout);
+            });
+            assert!(inner.allocations >= 1 && inner.bytes >= 4096);
+            let first = allocator.realloc(first, layout, 8192);
+            allocator.dealloc(first, Layout::from_size_align(8192, 8).unwrap());
+        });
+        // other tests may allocate at the same time if the allocator is global
+        assert!(usage.allocations >= 3);
+        assert!(usage.bytes >= 4096 + 4096 + 8192);
+        assert!(usage.peak >= 8192);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512B");
+        assert_eq!(format_bytes(1536), "1.5KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0MiB");
+    }
+}
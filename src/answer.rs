@@ -0,0 +1,143 @@
+use std::fmt;
+
+use crate::ocr;
+
+/// A puzzle answer as a solution computes it. Answers are equal when they
+/// would be submitted the same way, so art equals the letters it shows.
+#[derive(Debug, Clone)]
+pub enum Answer {
+    /// Wide enough for the answers of any integer type
+    Number(i128),
+    Text(String),
+    /// Letters drawn with `#` on a grid, one line per row
+    Art(String),
+}
+
+impl Answer {
+    pub fn art(art: impl Into<String>) -> Self {
+        Self::Art(art.into())
+    }
+
+    /// Interpret what a solution printed: several lines are art, a single line
+    /// a number if it parses as one and text otherwise.
+    pub fn from_output(output: &str) -> Self {
+        let output = output.trim_matches('\n').trim_end();
+        if output.lines().count() > 1 {
+            return Self::Art(output.to_string());
+        }
+        let output = output.trim();
+        match output.parse() {
+            Ok(number) => Self::Number(number),
+            Err(_) => Self::Text(output.to_string()),
+        }
+    }
+
+    /// The answer as it would be submitted, reading the letters of art.
+    pub fn to_text(&self) -> Result<String, ocr::Error> {
+        match self {
+            Self::Number(number) => Ok(number.to_string()),
+            Self::Text(text) => Ok(text.trim().to_string()),
+            Self::Art(art) => ocr::recognize(art),
+        }
+    }
+
+    /// The answer as it would be submitted, or the drawing if art can't be
+    /// read.
+    pub fn submission(&self) -> String {
+        self.to_text().unwrap_or_else(|_| self.to_string())
+    }
+}
+
+impl PartialEq for Answer {
+    fn eq(&self, other: &Self) -> bool {
+        self.submission() == other.submission()
+    }
+}
+
+impl Eq for Answer {}
+
+/// Art is shown as drawn; use `to_text` for the letters.
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(number) => write!(f, "{}", number),
+            Self::Text(text) => write!(f, "{}", text),
+            Self::Art(art) => write!(f, "{}", art.trim_end()),
+        }
+    }
+}
+
+macro_rules! from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(number: $t) -> Self {
+                    Self::Number(number as i128)
+                }
+            }
+        )*
+    };
+}
+
+from_integer!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl From<String> for Answer {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_text() {
+        assert_eq!(Answer::from(1_675_u64).to_text().unwrap(), "1675");
+        assert_eq!(Answer::from(-3_i32).to_text().unwrap(), "-3");
+        assert_eq!(Answer::from(" abc\n").to_text().unwrap(), "abc");
+        let art = Answer::art(
+            "\
+.##..###.
+#..#.#..#
+#..#.###.
+####.#..#
+#..#.#..#
+#..#.###.",
+        );
+        assert_eq!(art.to_text().unwrap(), "AB");
+        assert!(art.to_string().starts_with(".##..###."));
+
+        assert_eq!(Answer::from_output("1675\n"), Answer::Number(1675));
+        assert_eq!(Answer::from_output(" abc \n"), Answer::from("abc"));
+        assert_eq!(Answer::from_output(&format!("\n{}\n", art)), art);
+    }
+
+    #[test]
+    fn test_eq() {
+        let art = Answer::art(
+            "\
+.##..###.
+#..#.#..#
+#..#.###.
+####.#..#
+#..#.#..#
+#..#.###.",
+        );
+        assert_eq!(art, Answer::from("AB"));
+        assert_ne!(art, Answer::from("BA"));
+        assert_eq!(Answer::from(1675_u32), Answer::from("1675"));
+        assert_ne!(Answer::from(1675_u32), Answer::from(1676_u32));
+
+        // art that can't be read is compared as drawn
+        let unreadable = Answer::art("#.#\n.#.");
+        assert_eq!(unreadable, Answer::art("#.#\n.#.\n"));
+        assert_ne!(unreadable, Answer::art(".#.\n#.#"));
+    }
+}
@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+
+use crate::utils::write_atomic;
+
+pub fn path() -> PathBuf {
+    crate::config::path().with_file_name("cache")
+}
+
+/// The kinds of pages the tool fetches, each with its own minimum interval
+/// between requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    Input,
+    Puzzle,
+    Leaderboard,
+    Stats,
+}
+
+impl Resource {
+    pub fn min_refresh(self) -> Duration {
+        match self {
+            // inputs never change once published
+            Self::Input => Duration::MAX,
+            // the page grows a second part once the first is solved
+            Self::Puzzle => Duration::from_secs(5 * 60),
+            // the site asks for no more than one request per 15 minutes
+            Self::Leaderboard | Self::Stats => Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Meta {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Unix timestamp of the last time the server was asked about this url
+    pub fetched_at: u64,
+}
+
+impl Meta {
+    pub fn new(url: &str, etag: Option<String>, last_modified: Option<String>) -> Self {
+        Self {
+            url: url.to_string(),
+            etag,
+            last_modified,
+            fetched_at: now(),
+        }
+    }
+
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(now().saturating_sub(self.fetched_at))
+    }
+
+    pub fn touch(&mut self) {
+        self.fetched_at = now();
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Responses stored on disk as a body file plus a json file of metadata,
+/// both named after the url they came from. Pages differ per account, so
+/// the tool keeps one such directory per session cookie.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self::new(path())
+    }
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// The cache for the account `session` logs into, in a subdirectory of
+    /// [`path`] named after a hash of the cookie.
+    pub fn for_session(session: &str) -> Self {
+        Self::new(path().join(account_dir(session)))
+    }
+
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+
+    fn key(url: &str) -> String {
+        let url = url
+            .trim_start_matches("https://")
+            .trim_start_matches("http://");
+        url.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect()
+    }
+
+    fn body_path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{}.body", Self::key(url)))
+    }
+
+    fn meta_path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", Self::key(url)))
+    }
+
+    pub fn load(&self, url: &str) -> Result<Option<(Meta, Vec<u8>)>, Error> {
+        let (meta_path, body_path) = (self.meta_path(url), self.body_path(url));
+        if !meta_path.exists() || !body_path.exists() {
+            return Ok(None);
+        }
+        let meta: Meta = serde_json::from_slice(&std::fs::read(meta_path)?)?;
+        let body = std::fs::read(body_path)?;
+        Ok(Some((meta, body)))
+    }
+
+    pub fn store(&self, meta: &Meta, body: &[u8]) -> Result<(), Error> {
+        std::fs::create_dir_all(&self.dir)?;
+        write_atomic(&self.body_path(&meta.url), body)?;
+        write_atomic(
+            &self.meta_path(&meta.url),
+            serde_json::to_string(meta)?.as_bytes(),
+        )?;
+        Ok(())
+    }
+
+    /// Remove every cached response, including those of other sessions kept
+    /// in subdirectories, returning how many there were.
+    pub fn clear(&self) -> Result<usize, Error> {
+        if !self.dir.exists() {
+            return Ok(0);
+        }
+        let mut count = 0;
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                count += Self::new(path.clone()).clear()?;
+                std::fs::remove_dir(path)?;
+                continue;
+            }
+            if path
+                .extension()
+                .map(|ext| ext == "json")
+                .unwrap_or_default()
+            {
+                count += 1;
+            }
+            std::fs::remove_file(path)?;
+        }
+        Ok(count)
+    }
+}
+
+/// FNV-1a of the session cookie: stable across builds, and keeps the cookie
+/// itself out of the file system.
+fn account_dir(session: &str) -> String {
+    let hash = session
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("corrupt cache metadata")]
+    Metadata(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_store_load_clear() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let cache = Cache::new(dir.to_owned());
+        let url = "https://adventofcode.com/2021/day/1";
+
+        assert!(cache.load(url).unwrap().is_none());
+        let meta = Meta::new(url, Some("\"abc\"".to_string()), None);
+        cache.store(&meta, b"<main></main>").unwrap();
+
+        let (loaded, body) = cache.load(url).unwrap().unwrap();
+        assert_eq!(loaded.etag.as_deref(), Some("\"abc\""));
+        assert_eq!(body, b"<main></main>");
+        assert!(loaded.age() < Resource::Puzzle.min_refresh());
+
+        assert_eq!(cache.clear().unwrap(), 1);
+        assert!(cache.load(url).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_sessions_are_kept_apart() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = Cache::new(temp.path().to_owned());
+        let first = Cache::new(temp.path().join(account_dir("first")));
+        let second = Cache::new(temp.path().join(account_dir("second")));
+        assert_ne!(first.dir(), second.dir());
+        assert_eq!(account_dir("first"), account_dir("first"));
+
+        let url = "https://adventofcode.com/2021/day/1/input";
+        first.store(&Meta::new(url, None, None), b"1\n2\n").unwrap();
+        assert!(first.load(url).unwrap().is_some());
+        assert!(second.load(url).unwrap().is_none());
+
+        second.store(&Meta::new(url, None, None), b"3\n").unwrap();
+        assert_eq!(root.clear().unwrap(), 2);
+        assert!(first.load(url).unwrap().is_none());
+        assert!(!first.dir().exists());
+    }
+}
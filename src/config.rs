@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 pub fn path() -> PathBuf {
@@ -9,12 +9,43 @@ pub fn path() -> PathBuf {
         .join("2021.json")
 }
 
+/// Environment variable that enables offline mode when set to anything but
+/// `0` or `false`.
+pub const OFFLINE_ENV: &str = "AOC_OFFLINE";
+
+/// Environment variable listing extra PEM root certificate files, separated
+/// like `PATH`.
+pub const CA_CERTS_ENV: &str = "AOC_CA_CERTS";
+
+/// Whether a value of `AOC_OFFLINE` turns offline mode on.
+fn enables_offline(value: Option<&str>) -> bool {
+    value.is_some_and(|value| !matches!(value, "" | "0" | "false"))
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Config {
     /// Session cookie
     pub session: String,
     /// Path to input files
     pub input_files: Option<PathBuf>,
+    /// Contact information (email, url) sent in the User-Agent header
+    #[serde(default)]
+    pub contact: Option<String>,
+    /// Name put in the header of generated crates
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Never touch the network, only use cached inputs and pages
+    #[serde(default)]
+    pub offline: bool,
+    /// HTTP(S) proxy url, falls back to `HTTPS_PROXY`/`HTTP_PROXY`
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Hosts reached without the proxy, falls back to `NO_PROXY`
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+    /// Extra PEM root certificates, falls back to `AOC_CA_CERTS`
+    #[serde(default)]
+    pub ca_certs: Vec<PathBuf>,
 }
 
 impl Config {
@@ -34,6 +65,56 @@ impl Config {
         serde_json::from_slice(&content).map_err(Into::into)
     }
 
+    /// Whether network access is disabled, either in the config or with the
+    /// `AOC_OFFLINE` environment variable.
+    pub fn is_offline(&self) -> bool {
+        self.offline || enables_offline(std::env::var(OFFLINE_ENV).ok().as_deref())
+    }
+
+    pub fn proxy(&self) -> Option<String> {
+        self.proxy.clone().or_else(|| {
+            ["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy"]
+                .iter()
+                .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        })
+    }
+
+    pub fn no_proxy(&self) -> Vec<String> {
+        if !self.no_proxy.is_empty() {
+            return self.no_proxy.clone();
+        }
+        ["NO_PROXY", "no_proxy"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok())
+            .map(|hosts| {
+                hosts
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|host| !host.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn ca_certs(&self) -> Vec<PathBuf> {
+        if !self.ca_certs.is_empty() {
+            return self.ca_certs.clone();
+        }
+        std::env::var_os(CA_CERTS_ENV)
+            .map(|paths| std::env::split_paths(&paths).collect())
+            .unwrap_or_default()
+    }
+
+    /// Default the input directory to `input` in the workspace at `root`
+    /// rather than in the current directory, unless one is configured. Used
+    /// by commands that run day binaries from the root, so both sides find
+    /// the same files wherever the command is run from.
+    pub fn in_workspace(mut self, root: &Path) -> Self {
+        self.input_files.get_or_insert_with(|| root.join("input"));
+        self
+    }
+
     pub fn input_files(&self) -> PathBuf {
         match self.input_files {
             Some(ref input_files) => input_files.to_owned(),
@@ -50,6 +131,15 @@ impl Config {
     pub fn input_for(&self, day: u8) -> PathBuf {
         self.input_files().join(format!("input-{:02}.txt", day))
     }
+
+    pub fn puzzle_for(&self, day: u8) -> PathBuf {
+        self.input_files().join(format!("puzzle-{:02}.html", day))
+    }
+
+    /// Expected answers, kept with the inputs since both differ per account.
+    pub fn answers_path(&self) -> PathBuf {
+        self.input_files().join("answers.toml")
+    }
 }
 
 #[derive(Debug, Error)]
@@ -59,3 +149,39 @@ pub enum Error {
     #[error(transparent)]
     CouldNotSerialize(#[from] serde_json::Error),
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_offline() {
+        assert!(!enables_offline(None));
+        for value in ["", "0", "false"] {
+            assert!(!enables_offline(Some(value)));
+        }
+        for value in ["1", "true", "yes"] {
+            assert!(enables_offline(Some(value)));
+        }
+
+        let config = Config {
+            offline: true,
+            ..Config::default()
+        };
+        assert!(config.is_offline());
+    }
+
+    #[test]
+    fn test_in_workspace() {
+        let root = Path::new("/workspace");
+        let config = Config::default().in_workspace(root);
+        assert_eq!(config.input_files(), root.join("input"));
+
+        let config = Config {
+            input_files: Some(PathBuf::from("/inputs")),
+            ..Config::default()
+        }
+        .in_workspace(root);
+        assert_eq!(config.input_files(), Path::new("/inputs"));
+    }
+}
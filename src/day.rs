@@ -1,70 +1,105 @@
-use std::{io::Write, path::PathBuf, str::FromStr};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use serde::Serialize;
 use thiserror::Error;
-use tinytemplate::TinyTemplate;
 use toml_edit::Document;
 
-use crate::config::Config;
+use crate::{
+    config::Config,
+    deps::{inherited, workspace_dependencies},
+    puzzle::Puzzle,
+    template::{Record, TemplateSet},
+    transaction::Transaction,
+    utils::{today, unified_diff, url_for_day},
+};
 
 const EXPECT_PACKAGE: &str = env!("CARGO_PKG_NAME");
 
-fn render_templates_into(
-    current_dir: &PathBuf,
-    day_dir: &PathBuf,
+/// Everything a template can refer to.
+#[derive(Debug, Serialize)]
+pub(crate) struct Context {
+    year: u16,
     day: u8,
-    day_name: &str,
-) -> Result<(), Error> {
-    #[derive(Serialize)]
-    struct Context {
-        day: u8,
-        package_name: String,
-    }
-
-    let context = Context {
-        day,
-        package_name: day_name.to_string(),
-    };
+    /// `day` with a leading zero, as used in crate names
+    day_padded: String,
+    package_name: String,
+    url: String,
+    /// Only known once the puzzle page has been fetched
+    title: Option<String>,
+    /// `YYYY-MM-DD`
+    pub(crate) date: String,
+    author: Option<String>,
+    /// Example inputs from the puzzle page, if it has been fetched
+    examples: Vec<String>,
+}
 
-    let template_dir = current_dir.join("day-template");
+impl Context {
+    pub(crate) fn new(config: &Config, day: u8, day_name: &str) -> Result<Self, Error> {
+        let puzzle = Puzzle::load(&config.puzzle_for(day))?.unwrap_or_default();
+        Ok(Self {
+            year: 2021,
+            day,
+            day_padded: format!("{:02}", day),
+            package_name: day_name.to_string(),
+            url: url_for_day(day),
+            title: puzzle.title,
+            date: today(),
+            author: config.author.clone(),
+            examples: puzzle.examples,
+        })
+    }
+}
 
-    for template in &["Cargo.toml", "src/lib.rs", "src/main.rs"] {
-        let mut tt = TinyTemplate::new();
-        let template_text =
-            std::fs::read_to_string(template_dir.join(format!("{}.tmpl", template)))?;
-        tt.add_template(&template, &template_text)
-            .map_err(|err| Error::Template(err, template.to_string()))?;
+/// Render the template set `template` for `day` in memory.
+pub(crate) fn render_templates(
+    current_dir: &Path,
+    template: &str,
+    context: &Context,
+) -> Result<Vec<(PathBuf, String)>, Error> {
+    TemplateSet::load(current_dir, template)?
+        .render(context)
+        .map_err(Into::into)
+}
 
-        let rendered_text = tt
-            .render(template, &context)
-            .map_err(|err| Error::Template(err, template.to_string()))?;
+pub(crate) fn get_package_name(manifest: &Document) -> Option<&str> {
+    manifest
+        .as_table()
+        .get("package")?
+        .as_table_like()?
+        .get("name")?
+        .as_value()?
+        .as_str()
+}
 
-        let mut file = std::fs::OpenOptions::new()
-            .write(true)
-            .create_new(true)
-            .open(day_dir.join(template))?;
-        file.write_all(rendered_text.as_bytes())?;
+/// The root of the workspace `start` is in: the nearest directory at or above
+/// it whose `Cargo.toml` is this package's, so commands work from inside a day
+/// crate too.
+pub fn workspace_root(start: &Path) -> Result<PathBuf, Error> {
+    for dir in start.ancestors() {
+        let cargo_toml_path = dir.join("Cargo.toml");
+        if !cargo_toml_path.is_file() {
+            continue;
+        }
+        let manifest = Document::from_str(&std::fs::read_to_string(&cargo_toml_path)?)?;
+        if get_package_name(&manifest) == Some(EXPECT_PACKAGE) {
+            return Ok(dir.to_owned());
+        }
     }
-    Ok(())
+    Err(Error::NoWorkspace(start.to_owned()))
 }
 
-fn add_create_to_workspace(current_dir: &PathBuf, crate_name: &str) -> Result<(), Error> {
+/// Parse the root `Cargo.toml`, making sure it belongs to this package.
+pub(crate) fn load_manifest(current_dir: &Path) -> Result<Document, Error> {
     let cargo_toml_path = current_dir.join("Cargo.toml");
     if !cargo_toml_path.exists() {
         Err(Error::NoCargoToml)?;
     }
 
-    let mut manifest = Document::from_str(&std::fs::read_to_string(&cargo_toml_path)?)?;
-
-    fn get_package_name(manifest: &Document) -> Option<&str> {
-        manifest
-            .as_table()
-            .get("package")?
-            .as_table_like()?
-            .get("name")?
-            .as_value()?
-            .as_str()
-    }
+    let manifest = Document::from_str(&std::fs::read_to_string(&cargo_toml_path)?)?;
 
     let package_name = get_package_name(&manifest).ok_or(Error::MalformedToml)?;
 
@@ -72,6 +107,11 @@ fn add_create_to_workspace(current_dir: &PathBuf, crate_name: &str) -> Result<()
         Err(Error::WrongPackage(package_name.to_string()))?;
     }
 
+    Ok(manifest)
+}
+
+/// The `[workspace] members` array, created if it is missing.
+pub(crate) fn members_mut(manifest: &mut Document) -> Result<&mut toml_edit::Array, Error> {
     let root_table = manifest.as_table_mut();
     let workspace = root_table
         .entry("workspace")
@@ -83,54 +123,333 @@ fn add_create_to_workspace(current_dir: &PathBuf, crate_name: &str) -> Result<()
             .or_insert(toml_edit::Item::Value(toml_edit::Value::Array(
                 Default::default(),
             )));
-    let members = members
+    members
         .as_value_mut()
         .ok_or(Error::MalformedToml)?
         .as_array_mut()
-        .ok_or(Error::MalformedToml)?;
+        .ok_or(Error::MalformedToml)
+}
 
-    if members.iter().any(|item| {
+fn member_position(members: &toml_edit::Array, crate_name: &str) -> Option<usize> {
+    members.iter().position(|item| {
         item.as_str()
             .map(|item_str| item_str == crate_name)
             .unwrap_or_default()
-    }) {
+    })
+}
+
+/// The root `Cargo.toml` with `crate_name` added to its workspace members. Nothing
+/// is written to disk.
+fn add_create_to_workspace(current_dir: &Path, crate_name: &str) -> Result<String, Error> {
+    let mut manifest = load_manifest(current_dir)?;
+    let members = members_mut(&mut manifest)?;
+
+    if member_position(members, crate_name).is_some() {
         Err(Error::CrateAlreadyExists(crate_name.to_string()))?;
     }
 
     members.push(crate_name);
 
-    std::fs::write(cargo_toml_path, manifest.to_string())?;
-    Ok(())
+    Ok(manifest.to_string())
+}
+
+/// The root `Cargo.toml` with `crate_name` dropped from its workspace members.
+/// Nothing is written to disk.
+fn remove_crate_from_workspace(current_dir: &Path, crate_name: &str) -> Result<String, Error> {
+    let mut manifest = load_manifest(current_dir)?;
+    let members = members_mut(&mut manifest)?;
+
+    let position = member_position(members, crate_name)
+        .ok_or_else(|| Error::NotAMember(crate_name.to_string()))?;
+    members.remove(position);
+
+    Ok(manifest.to_string())
+}
+
+/// The names listed in `[workspace] members` of the root `Cargo.toml`.
+pub fn workspace_members(current_dir: &Path) -> Result<Vec<String>, Error> {
+    let cargo_toml_path = current_dir.join("Cargo.toml");
+    if !cargo_toml_path.exists() {
+        Err(Error::NoCargoToml)?;
+    }
+
+    let manifest = Document::from_str(&std::fs::read_to_string(&cargo_toml_path)?)?;
+    let members = match manifest
+        .as_table()
+        .get("workspace")
+        .and_then(|workspace| workspace.as_table_like()?.get("members"))
+    {
+        Some(members) => members,
+        None => return Ok(Vec::new()),
+    };
+    let members = members
+        .as_value()
+        .and_then(toml_edit::Value::as_array)
+        .ok_or(Error::MalformedToml)?;
+
+    Ok(members
+        .iter()
+        .filter_map(|item| item.as_str().map(str::to_string))
+        .collect())
+}
+
+/// `<dir>/<day_name>-<timestamp>`, with a counter appended if an earlier run
+/// in the same second already used that name.
+fn timestamped(dir: PathBuf, day_name: &str) -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    unused_path(&dir, &format!("{}-{}", day_name, timestamp))
+}
+
+fn unused_path(dir: &Path, name: &str) -> PathBuf {
+    let mut path = dir.join(name);
+    let mut counter = 1;
+    while path.exists() {
+        path = dir.join(format!("{}-{}", name, counter));
+        counter += 1;
+    }
+    path
+}
+
+/// Where `--force` moves files it is about to overwrite.
+fn backup_dir(current_dir: &Path, day_name: &str) -> PathBuf {
+    timestamped(current_dir.join(".backup"), day_name)
+}
+
+/// Where `archive` moves a day's crate.
+fn archive_dir(current_dir: &Path, day_name: &str) -> PathBuf {
+    timestamped(current_dir.join(".archive"), day_name)
+}
+
+/// Make sure the workspace declares every dependency the rendered crate and
+/// the root package inherit from it.
+fn check_inherited(
+    current_dir: &Path,
+    manifest: Option<&str>,
+    rendered: &[(PathBuf, String)],
+) -> Result<(), Error> {
+    let root_manifest = match manifest {
+        Some(manifest) => Document::from_str(manifest)?,
+        None => load_manifest(current_dir)?,
+    };
+    let mut wanted = inherited(&root_manifest);
+    if let Some((_, text)) = rendered
+        .iter()
+        .find(|(path, _)| path == Path::new("Cargo.toml"))
+    {
+        wanted.extend(inherited(&Document::from_str(text)?));
+    }
+    let declared = workspace_dependencies(&root_manifest);
+    match wanted.into_iter().find(|name| !declared.contains(name)) {
+        Some(name) => Err(Error::NotInWorkspace(name)),
+        None => Ok(()),
+    }
 }
 
-pub fn initialize(_config: &Config, day: u8, force: bool) -> Result<(), Error> {
-    let current_dir = std::env::current_dir()?;
+/// A file `initialize` writes, relative to the workspace root.
+#[derive(Debug)]
+struct Planned {
+    path: PathBuf,
+    text: String,
+    /// Where `--force` moves the previous version, relative to the backup
+    /// directory. Files without one are simply replaced.
+    backup: Option<PathBuf>,
+}
+
+/// Everything `initialize` writes: the rendered crate, the record of how it
+/// was rendered and, if it changed, the workspace manifest.
+fn planned_files(
+    day_name: &str,
+    template: &str,
+    date: &str,
+    rendered: &[(PathBuf, String)],
+    manifest: Option<String>,
+) -> Vec<Planned> {
+    let mut files: Vec<Planned> = rendered
+        .iter()
+        .map(|(path, text)| Planned {
+            path: Path::new(day_name).join(path),
+            text: text.clone(),
+            backup: Some(path.clone()),
+        })
+        .collect();
+    files.push(Planned {
+        path: Path::new(day_name).join(Record::FILE),
+        text: Record::new(template, date, rendered).to_json(),
+        backup: None,
+    });
+    if let Some(manifest) = manifest {
+        files.push(Planned {
+            path: PathBuf::from("Cargo.toml"),
+            text: manifest,
+            backup: None,
+        });
+    }
+    files
+}
+
+/// Unified diffs of every file in `files` against the current tree. Files
+/// that would stay the same are left out.
+fn preview(current_dir: &Path, files: &[Planned]) -> Result<Vec<(PathBuf, String)>, Error> {
+    let mut diffs = Vec::new();
+    for file in files {
+        let target = current_dir.join(&file.path);
+        let existing = if target.exists() {
+            std::fs::read_to_string(&target)?
+        } else {
+            String::new()
+        };
+        if existing != file.text {
+            let diff = unified_diff(&file.path.to_string_lossy(), &existing, &file.text);
+            diffs.push((file.path.clone(), diff));
+        }
+    }
+    Ok(diffs)
+}
+
+/// Write `files`, rolling back on the first error. Files that would change
+/// and have a backup path are moved to `backup_dir` first; their diffs are
+/// returned.
+fn write_files(
+    current_dir: &Path,
+    files: &[Planned],
+    backup_dir: &Path,
+) -> Result<Vec<String>, Error> {
+    let mut diffs = Vec::new();
+    let mut transaction = Transaction::default();
+    for file in files {
+        let target = current_dir.join(&file.path);
+        if !target.exists() {
+            transaction.create_file(&target, &file.text)?;
+            continue;
+        }
+        let existing = std::fs::read_to_string(&target)?;
+        if existing == file.text {
+            continue;
+        }
+        match &file.backup {
+            Some(backup) => {
+                transaction.move_path(&target, &backup_dir.join(backup))?;
+                let label = file.path.to_string_lossy();
+                diffs.push(unified_diff(&label, &existing, &file.text));
+                transaction.create_file(&target, &file.text)?;
+            }
+            None => transaction.replace_file(&target, &file.text)?,
+        }
+    }
+    transaction.commit();
+    Ok(diffs)
+}
+
+/// Create the crate for `day` from `template` and add it to the workspace. With
+/// `dry_run` everything is staged as usual, so the same errors are reported,
+/// but the changes are only printed as diffs.
+pub fn initialize(
+    config: &Config,
+    day: u8,
+    force: bool,
+    dry_run: bool,
+    template: &str,
+) -> Result<(), Error> {
+    let current_dir = workspace_root(&std::env::current_dir()?)?;
     let day_name = format!("day{:02}", day);
     let day_dir = current_dir.join(&day_name);
 
     if day_dir.exists() && !force {
         Err(Error::DayExist(day))?;
     }
-    std::fs::create_dir_all(day_dir.join("src"))?;
 
-    add_create_to_workspace(&current_dir, &day_name)?;
+    // stage everything in memory first, so most errors leave the tree untouched
+    let manifest = match add_create_to_workspace(&current_dir, &day_name) {
+        Ok(manifest) => Some(manifest),
+        // with --force the workspace entry is left alone
+        Err(Error::CrateAlreadyExists(_)) if force => None,
+        Err(err) => return Err(err),
+    };
+    let context = Context::new(config, day, &day_name)?;
+    let rendered = render_templates(&current_dir, template, &context)?;
+    check_inherited(&current_dir, manifest.as_deref(), &rendered)?;
+
+    let files = planned_files(&day_name, template, &context.date, &rendered, manifest);
 
-    // render templates, creating new sub-crate
-    render_templates_into(&current_dir, &day_dir, day, &day_name)?;
+    if dry_run {
+        for (_, diff) in preview(&current_dir, &files)? {
+            print!("{}", diff);
+        }
+        return Ok(());
+    }
+
+    let backup_dir = backup_dir(&current_dir, &day_name);
+    let diffs = write_files(&current_dir, &files, &backup_dir)?;
+    if !diffs.is_empty() {
+        println!("previous files moved to {}", backup_dir.display());
+        for diff in diffs {
+            print!("{}", diff);
+        }
+    }
 
     Ok(())
 }
 
+/// Take the crate for `day` out of the workspace. With `archive` the crate is
+/// moved to `.archive/` and its path returned, otherwise it is deleted. Inputs
+/// and puzzle pages live outside the workspace and are never touched.
+pub fn remove(day: u8, archive: bool) -> Result<Option<PathBuf>, Error> {
+    let current_dir = workspace_root(&std::env::current_dir()?)?;
+    let day_name = format!("day{:02}", day);
+    let day_dir = current_dir.join(&day_name);
+
+    let manifest = match remove_crate_from_workspace(&current_dir, &day_name) {
+        Ok(manifest) => Some(manifest),
+        // a crate that never made it into the workspace can still be cleaned up
+        Err(Error::NotAMember(_)) if day_dir.exists() => None,
+        Err(Error::NotAMember(_)) => return Err(Error::DayMissing(day)),
+        Err(err) => return Err(err),
+    };
+
+    // deleting can't be rolled back, so move the crate aside first and only
+    // delete it once the manifest has been updated
+    let destination = if archive {
+        archive_dir(&current_dir, &day_name)
+    } else {
+        backup_dir(&current_dir, &day_name)
+    };
+    let mut transaction = Transaction::default();
+    if day_dir.exists() {
+        transaction.move_path(&day_dir, &destination)?;
+    }
+    if let Some(manifest) = manifest {
+        transaction.replace_file(&current_dir.join("Cargo.toml"), &manifest)?;
+    }
+    transaction.commit();
+
+    if archive {
+        return Ok(Some(destination).filter(|destination| destination.exists()));
+    }
+    if destination.exists() {
+        std::fs::remove_dir_all(&destination)?;
+    }
+    Ok(None)
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("directory for day {0} already exists")]
     DayExist(u8),
     #[error(transparent)]
     Io(#[from] std::io::Error),
-    #[error("template error for {1}")]
-    Template(#[source] tinytemplate::error::Error, String),
+    #[error(transparent)]
+    Template(#[from] crate::template::Error),
     #[error("Cargo.toml not found")]
     NoCargoToml,
+    #[error(
+        "no {} workspace at or above {}",
+        EXPECT_PACKAGE,
+        .0.display()
+    )]
+    NoWorkspace(PathBuf),
     #[error("could not parse Cargo.toml")]
     ParseToml(#[from] toml_edit::TomlError),
     #[error("Cargo.toml is malformed")]
@@ -142,4 +461,165 @@ pub enum Error {
     WrongPackage(String),
     #[error("crate already exists in workspace: {0}")]
     CrateAlreadyExists(String),
+    #[error("crate is not a workspace member: {0}")]
+    NotAMember(String),
+    #[error("there is no crate for day {0}")]
+    DayMissing(u8),
+    #[error("{0} is inherited from the workspace, but [workspace.dependencies] doesn't declare it; run sync-deps or add it")]
+    NotInWorkspace(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unused_path() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("day05-100")).unwrap();
+        std::fs::create_dir_all(dir.join("day05-100-1")).unwrap();
+        assert_eq!(unused_path(dir, "day05-100"), dir.join("day05-100-2"));
+        assert_eq!(unused_path(dir, "day05-101"), dir.join("day05-101"));
+    }
+
+    /// Every built-in set renders to crates that pass clippy with warnings
+    /// denied, tests included. Slow the first time: it builds in its own
+    /// target directory so it can run while cargo holds the main one.
+    #[test]
+    fn test_templates_compile_without_warnings() {
+        let repo = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+
+        let user_dir = tempfile::tempdir().unwrap();
+        let sets = [(8, "plain"), (9, "grid"), (10, "single-line-csv")];
+        let mut manifest = load_manifest(repo).unwrap();
+        for table in ["package", "dependencies", "dev-dependencies", "features"] {
+            manifest.as_table_mut().remove(table);
+        }
+        let members = members_mut(&mut manifest).unwrap();
+        members.clear();
+        for (day, set) in sets {
+            let day_name = format!("day{:02}", day);
+            members.push(day_name.as_str());
+            let context = Context {
+                year: 2021,
+                day,
+                day_padded: format!("{:02}", day),
+                package_name: day_name.clone(),
+                url: url_for_day(day),
+                title: Some(set.to_string()),
+                date: "2021-12-01".to_string(),
+                author: None,
+                examples: vec!["1,2\n3,4".to_string(), "#.\n\"#.\n".to_string()],
+            };
+            let rendered = TemplateSet::load_from(user_dir.path(), repo, set)
+                .unwrap()
+                .render(&context)
+                .unwrap();
+            for (path, text) in rendered {
+                let path = dir.join(&day_name).join(path);
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(path, text).unwrap();
+            }
+        }
+        manifest["workspace"]["resolver"] = toml_edit::value("2");
+        manifest["workspace"]["dependencies"]["aoc2021"]["path"] =
+            toml_edit::value(repo.to_string_lossy().as_ref());
+        std::fs::write(dir.join("Cargo.toml"), manifest.to_string()).unwrap();
+        // the same versions as the workspace, which are built and cached already
+        if repo.join("Cargo.lock").is_file() {
+            std::fs::copy(repo.join("Cargo.lock"), dir.join("Cargo.lock")).unwrap();
+        }
+
+        let output = std::process::Command::new(env!("CARGO"))
+            .args([
+                "clippy",
+                "--offline",
+                "--all-targets",
+                "--",
+                "-D",
+                "warnings",
+            ])
+            .current_dir(dir)
+            .env(
+                "CARGO_TARGET_DIR",
+                repo.join("target").join("template-check"),
+            )
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    #[test]
+    fn test_preview_lists_every_written_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("day03")).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "[workspace]\n").unwrap();
+        let rendered = [
+            (PathBuf::from("Cargo.toml"), "[package]\n".to_string()),
+            (PathBuf::from("src/lib.rs"), "// day 3\n".to_string()),
+        ];
+        let manifest = "[workspace]\nmembers = [\"day03\"]\n".to_string();
+        let files = planned_files("day03", "default", "2021-12-03", &rendered, Some(manifest));
+
+        let previewed: Vec<_> = preview(dir, &files)
+            .unwrap()
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        let planned: Vec<_> = files.iter().map(|file| file.path.clone()).collect();
+        assert_eq!(previewed, planned);
+        assert!(previewed.contains(&Path::new("day03").join(Record::FILE)));
+
+        write_files(dir, &files, &dir.join(".backup")).unwrap();
+        for file in &files {
+            assert_eq!(
+                std::fs::read_to_string(dir.join(&file.path)).unwrap(),
+                file.text
+            );
+        }
+        assert!(preview(dir, &files).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_workspace_members_round_trip() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let original = format!(
+            "[package]\nname = \"{}\"\n\n[workspace]\nmembers = [\"day01\", \"day02\"]\n",
+            EXPECT_PACKAGE
+        );
+        std::fs::write(dir.join("Cargo.toml"), &original).unwrap();
+
+        let added = add_create_to_workspace(dir, "day03").unwrap();
+        std::fs::write(dir.join("Cargo.toml"), &added).unwrap();
+        assert_eq!(workspace_members(dir).unwrap(), ["day01", "day02", "day03"]);
+        assert!(matches!(
+            add_create_to_workspace(dir, "day03"),
+            Err(Error::CrateAlreadyExists(_))
+        ));
+
+        let removed = remove_crate_from_workspace(dir, "day02").unwrap();
+        std::fs::write(dir.join("Cargo.toml"), &removed).unwrap();
+        assert_eq!(workspace_members(dir).unwrap(), ["day01", "day03"]);
+        assert!(matches!(
+            remove_crate_from_workspace(dir, "day02"),
+            Err(Error::NotAMember(_))
+        ));
+
+        std::fs::create_dir_all(dir.join("day03/src")).unwrap();
+        std::fs::write(
+            dir.join("day03/Cargo.toml"),
+            "[package]\nname = \"day03\"\n",
+        )
+        .unwrap();
+        assert_eq!(workspace_root(&dir.join("day03/src")).unwrap(), dir);
+    }
 }
@@ -0,0 +1,487 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use path_absolutize::Absolutize;
+use thiserror::Error;
+use toml_edit::{Document, InlineTable, Item, Table};
+
+use crate::{
+    day::{load_manifest, workspace_members},
+    transaction::Transaction,
+    utils::unified_diff,
+};
+
+const TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// The part of a dependency the workspace can take over.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Spec {
+    Version(String),
+    /// `path` relative to the workspace root, with the version it is
+    /// published under, if any
+    Path {
+        path: PathBuf,
+        version: Option<String>,
+    },
+}
+
+impl Spec {
+    fn to_item(&self) -> Item {
+        match self {
+            Self::Version(version) => toml_edit::value(version.as_str()),
+            Self::Path { path, version } => {
+                let mut table = InlineTable::default();
+                if let Some(version) = version {
+                    table.get_or_insert("version", version.as_str());
+                }
+                table.get_or_insert("path", path.to_string_lossy().as_ref());
+                toml_edit::value(table)
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Version(version) => version.clone(),
+            Self::Path {
+                path,
+                version: None,
+            } => format!("path {}", path.display()),
+            Self::Path {
+                path,
+                version: Some(version),
+            } => format!("{} at path {}", version, path.display()),
+        }
+    }
+}
+
+/// The spec of a member's dependency, if it is a plain version or path
+/// dependency the workspace can declare instead. Anything else, like git
+/// dependencies or renamed packages, is left alone.
+fn spec(item: &Item, crate_dir: &Path, root: &Path) -> Option<Spec> {
+    if let Some(version) = item.as_str() {
+        return Some(Spec::Version(version.to_string()));
+    }
+    let table = item.as_table_like()?;
+    if table
+        .iter()
+        .any(|(key, _)| !matches!(key, "version" | "path" | "features" | "optional"))
+    {
+        return None;
+    }
+    if let Some(path) = table.get("path").and_then(Item::as_str) {
+        let path = crate_dir.join(path);
+        let path = path.absolutize().ok()?;
+        let root = root.absolutize().ok()?;
+        let relative = path.strip_prefix(&root).ok()?;
+        return Some(Spec::Path {
+            path: if relative.as_os_str().is_empty() {
+                PathBuf::from(".")
+            } else {
+                relative.to_owned()
+            },
+            version: table
+                .get("version")
+                .and_then(Item::as_str)
+                .map(str::to_string),
+        });
+    }
+    table
+        .get("version")
+        .and_then(Item::as_str)
+        .map(|version| Spec::Version(version.to_string()))
+}
+
+/// Numeric components of a version requirement like `^0.5.11`, for picking
+/// the newest of several.
+fn version_key(version: &str) -> Option<Vec<u64>> {
+    version
+        .trim()
+        .trim_start_matches(['^', '=', '~'])
+        .split('.')
+        .map(|part| part.trim().parse().ok())
+        .collect()
+}
+
+/// `{ workspace = true }`, keeping the member's own `features` and `optional`.
+fn inherit(item: &Item) -> Item {
+    let mut table = InlineTable::default();
+    table.get_or_insert("workspace", true);
+    if let Some(existing) = item.as_table_like() {
+        for key in ["features", "optional"] {
+            if let Some(value) = existing.get(key).and_then(Item::as_value) {
+                table.get_or_insert(key, value.clone());
+            }
+        }
+    }
+    toml_edit::value(table)
+}
+
+fn inherits(item: &Item) -> bool {
+    item.as_table_like()
+        .and_then(|table| table.get("workspace"))
+        .and_then(Item::as_bool)
+        .unwrap_or_default()
+}
+
+fn workspace_dependencies_mut(manifest: &mut Document) -> Result<&mut Table, Error> {
+    manifest
+        .as_table_mut()
+        .entry("workspace")
+        .or_insert(Item::Table(Table::new()))
+        .as_table_mut()
+        .ok_or(Error::Malformed("workspace"))?
+        .entry("dependencies")
+        .or_insert(Item::Table(Table::new()))
+        .as_table_mut()
+        .ok_or(Error::Malformed("workspace.dependencies"))
+}
+
+/// Names declared in `[workspace.dependencies]`.
+pub(crate) fn workspace_dependencies(manifest: &Document) -> Vec<String> {
+    manifest
+        .as_table()
+        .get("workspace")
+        .and_then(|workspace| workspace.as_table_like()?.get("dependencies"))
+        .and_then(Item::as_table_like)
+        .map(|table| table.iter().map(|(name, _)| name.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Names of the dependencies `manifest` inherits from the workspace.
+pub(crate) fn inherited(manifest: &Document) -> Vec<String> {
+    TABLES
+        .iter()
+        .filter_map(|name| manifest.as_table().get(name)?.as_table_like())
+        .flat_map(|table| {
+            table
+                .iter()
+                .filter(|(_, item)| inherits(item))
+                .map(|(name, _)| name.to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Names of the dependencies `manifest` pins itself although the workspace
+/// declares them.
+pub(crate) fn not_inherited(
+    manifest: &Document,
+    declared: &[String],
+    crate_dir: &Path,
+    root: &Path,
+) -> Vec<String> {
+    TABLES
+        .iter()
+        .filter_map(|name| manifest.as_table().get(name)?.as_table_like())
+        .flat_map(|table| {
+            table
+                .iter()
+                .filter(|(name, item)| {
+                    declared.iter().any(|declared| declared == name)
+                        && !inherits(item)
+                        && spec(item, crate_dir, root).is_some()
+                })
+                .map(|(name, _)| name.to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Have `manifest` inherit every dependency it pins although the workspace
+/// declares it.
+pub(crate) fn inherit_declared(
+    manifest: &mut Document,
+    declared: &[String],
+    crate_dir: &Path,
+    root: &Path,
+) {
+    for table_name in TABLES {
+        let table = match manifest
+            .as_table_mut()
+            .get_mut(table_name)
+            .and_then(Item::as_table_like_mut)
+        {
+            Some(table) => table,
+            None => continue,
+        };
+        for name in declared.iter() {
+            let item = match table.get_mut(name) {
+                Some(item) => item,
+                None => continue,
+            };
+            if !inherits(item) && spec(item, crate_dir, root).is_some() {
+                *item = inherit(item);
+            }
+        }
+    }
+}
+
+/// A dependency members pinned differently.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub name: String,
+    pub specs: Vec<String>,
+    /// What the workspace declares now, `None` if the members were left alone
+    pub chosen: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Changed manifests, relative to the workspace root, with their diffs
+    pub changed: Vec<(PathBuf, String)>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Move the version and path dependencies of every workspace member into
+/// `[workspace.dependencies]` and have the members inherit them, so each
+/// dependency is pinned in one place. Where members disagree the newest
+/// version wins; a dependency the workspace already declares keeps its
+/// version. The root package inherits whatever the workspace declares too,
+/// but its other dependencies stay its own. Nothing is written with `dry_run`.
+pub fn sync_deps(root: &Path, dry_run: bool) -> Result<Report, Error> {
+    let root_text = std::fs::read_to_string(root.join("Cargo.toml"))?;
+    let mut root_manifest = load_manifest(root)?;
+
+    let mut members = Vec::new();
+    for name in workspace_members(root)? {
+        let path = root.join(&name).join("Cargo.toml");
+        if !path.is_file() {
+            continue;
+        }
+        let text = std::fs::read_to_string(&path)?;
+        let manifest = Document::from_str(&text).map_err(|err| Error::Parse(err, path.clone()))?;
+        members.push((PathBuf::from(name).join("Cargo.toml"), text, manifest));
+    }
+
+    // every spec the members use for dependencies the workspace doesn't have yet
+    let declared = workspace_dependencies(&root_manifest);
+    let mut wanted: BTreeMap<String, Vec<Spec>> = BTreeMap::new();
+    let mut pinned: BTreeMap<String, Vec<Spec>> = BTreeMap::new();
+    for (path, _, manifest) in members.iter() {
+        let crate_dir = root.join(path.parent().unwrap_or(path));
+        for table in TABLES
+            .iter()
+            .filter_map(|name| manifest.as_table().get(name)?.as_table_like())
+        {
+            for (name, item) in table.iter() {
+                let spec = match spec(item, &crate_dir, root) {
+                    Some(spec) => spec,
+                    None => continue,
+                };
+                let specs = if declared.iter().any(|declared| declared == name) {
+                    pinned.entry(name.to_string()).or_default()
+                } else {
+                    wanted.entry(name.to_string()).or_default()
+                };
+                if !specs.contains(&spec) {
+                    specs.push(spec);
+                }
+            }
+        }
+    }
+
+    // the root package's own dependencies only count where they are shared
+    for table in TABLES
+        .iter()
+        .filter_map(|name| root_manifest.as_table().get(name)?.as_table_like())
+    {
+        for (name, item) in table.iter() {
+            let specs = match (pinned.get_mut(name), wanted.get_mut(name)) {
+                (Some(specs), _) | (None, Some(specs)) => specs,
+                (None, None) if declared.iter().any(|declared| declared == name) => {
+                    pinned.entry(name.to_string()).or_default()
+                }
+                (None, None) => continue,
+            };
+            if let Some(spec) = spec(item, root, root) {
+                if !specs.contains(&spec) {
+                    specs.push(spec);
+                }
+            }
+        }
+    }
+
+    let mut report = Report::default();
+    let declarations = workspace_dependencies_mut(&mut root_manifest)?;
+    for (name, specs) in pinned {
+        let chosen = declarations
+            .get(&name)
+            .map(|item| match spec(item, root, root) {
+                Some(spec) => spec.describe(),
+                None => item.to_string().trim().to_string(),
+            });
+        if specs.iter().any(|spec| Some(spec.describe()) != chosen) {
+            report.conflicts.push(Conflict {
+                name,
+                specs: specs.iter().map(Spec::describe).collect(),
+                chosen,
+            });
+        }
+    }
+    for (name, specs) in wanted {
+        let chosen = if specs.len() == 1 {
+            specs.first().cloned()
+        } else if specs.iter().all(|spec| matches!(spec, Spec::Version(_))) {
+            specs
+                .iter()
+                .max_by_key(|spec| match spec {
+                    Spec::Version(version) => version_key(version),
+                    Spec::Path { .. } => None,
+                })
+                .cloned()
+        } else {
+            None
+        };
+        if specs.len() > 1 {
+            report.conflicts.push(Conflict {
+                name: name.clone(),
+                specs: specs.iter().map(Spec::describe).collect(),
+                chosen: chosen.as_ref().map(Spec::describe),
+            });
+        }
+        if let Some(chosen) = chosen {
+            declarations.insert(&name, chosen.to_item());
+        }
+    }
+
+    // then have the members inherit everything the workspace declares
+    let declared = workspace_dependencies(&root_manifest);
+    let mut writes = Vec::new();
+    for (path, text, mut manifest) in members {
+        let crate_dir = root.join(path.parent().unwrap_or(&path));
+        inherit_declared(&mut manifest, &declared, &crate_dir, root);
+        let new_text = manifest.to_string();
+        if new_text != text {
+            report.changed.push((
+                path.clone(),
+                unified_diff(&path.to_string_lossy(), &text, &new_text),
+            ));
+            writes.push((root.join(&path), new_text));
+        }
+    }
+    inherit_declared(&mut root_manifest, &declared, root, root);
+    let new_root_text = root_manifest.to_string();
+    if new_root_text != root_text {
+        report.changed.insert(
+            0,
+            (
+                PathBuf::from("Cargo.toml"),
+                unified_diff("Cargo.toml", &root_text, &new_root_text),
+            ),
+        );
+        writes.push((root.join("Cargo.toml"), new_root_text));
+    }
+
+    if !dry_run {
+        let mut transaction = Transaction::default();
+        for (path, text) in writes {
+            transaction.replace_file(&path, &text)?;
+        }
+        transaction.commit();
+    }
+    Ok(report)
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Workspace(#[from] crate::day::Error),
+    #[error("could not parse {}", .1.display())]
+    Parse(#[source] toml_edit::TomlError, PathBuf),
+    #[error("{0} in the root Cargo.toml is not a table")]
+    Malformed(&'static str),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sync_deps() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        for day in ["day01", "day02"] {
+            std::fs::create_dir_all(dir.join(day)).unwrap();
+        }
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"aoc2021\"\n\n[dependencies]\nitertools = \"0.10.0\"\nreqwest = \"0.11.7\"\n\n[workspace]\nmembers = [\"day01\", \"day02\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("day01/Cargo.toml"),
+            "[package]\nname = \"day01\"\n\n[dependencies]\naoc2021 = { path = \"..\" }\nitertools = \"0.10.1\"\nserde = { version = \"1\", features = [\"derive\"] }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("day02/Cargo.toml"),
+            "[package]\nname = \"day02\"\n\n[dependencies]\naoc2021 = { path = \"..\" }\nitertools = \"0.10.3\"\nlocal = { git = \"https://example.com/local\" }\n",
+        )
+        .unwrap();
+
+        let report = sync_deps(dir, false).unwrap();
+        assert_eq!(report.changed.len(), 3);
+        assert_eq!(
+            report.conflicts,
+            [Conflict {
+                name: "itertools".to_string(),
+                specs: vec![
+                    "0.10.1".to_string(),
+                    "0.10.3".to_string(),
+                    "0.10.0".to_string()
+                ],
+                chosen: Some("0.10.3".to_string()),
+            }]
+        );
+
+        let root =
+            Document::from_str(&std::fs::read_to_string(dir.join("Cargo.toml")).unwrap()).unwrap();
+        assert_eq!(
+            workspace_dependencies(&root),
+            ["aoc2021", "itertools", "serde"]
+        );
+        assert_eq!(inherited(&root), ["itertools"]);
+        assert_eq!(root["dependencies"]["reqwest"].as_str(), Some("0.11.7"));
+        let day01 = std::fs::read_to_string(dir.join("day01/Cargo.toml")).unwrap();
+        assert!(day01.contains("serde = { workspace = true, features = [\"derive\"] }"));
+        let day02 =
+            Document::from_str(&std::fs::read_to_string(dir.join("day02/Cargo.toml")).unwrap())
+                .unwrap();
+        assert_eq!(inherited(&day02), ["aoc2021", "itertools"]);
+
+        assert_eq!(sync_deps(dir, false).unwrap().changed.len(), 0);
+    }
+
+    #[test]
+    fn test_sync_deps_keeps_path_and_version() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("day01")).unwrap();
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"aoc2021\"\n\n[workspace]\nmembers = [\"day01\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("day01/Cargo.toml"),
+            "[package]\nname = \"day01\"\n\n[dependencies]\naoc2021 = { path = \"..\", version = \"0.1.0\" }\n",
+        )
+        .unwrap();
+
+        let report = sync_deps(dir, false).unwrap();
+        assert!(report.conflicts.is_empty());
+        let root =
+            Document::from_str(&std::fs::read_to_string(dir.join("Cargo.toml")).unwrap()).unwrap();
+        let declared = &root["workspace"]["dependencies"]["aoc2021"];
+        assert_eq!(declared["path"].as_str(), Some("."));
+        assert_eq!(declared["version"].as_str(), Some("0.1.0"));
+
+        assert_eq!(sync_deps(dir, false).unwrap().changed.len(), 0);
+    }
+}
@@ -0,0 +1,400 @@
+use std::{fmt, path::Path, str::FromStr};
+
+use thiserror::Error;
+use toml_edit::Document;
+
+use crate::{
+    day::{get_package_name, load_manifest, members_mut, workspace_members},
+    deps::{inherit_declared, inherited, not_inherited, workspace_dependencies},
+    transaction::Transaction,
+};
+
+/// An inconsistency between the root `Cargo.toml` and the day crates on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// Listed in `[workspace] members` without a directory
+    MissingDir(String),
+    /// A `dayNN` crate that isn't a workspace member
+    NotMember(String),
+    /// `[workspace] members` isn't sorted
+    Unsorted,
+    /// Listed in `[workspace] members` more than once
+    Duplicate(String),
+    /// The crate's package name isn't its directory's name
+    PackageName { dir: String, name: String },
+    /// `const DAY` in `main.rs` isn't the crate's day
+    DayConstant {
+        dir: String,
+        found: u8,
+        expected: u8,
+    },
+    /// The crate, or the root package as `.`, pins its own version of a
+    /// dependency the workspace declares
+    NotInherited { dir: String, name: String },
+    /// The crate, or the root package as `.`, inherits a dependency the
+    /// workspace doesn't declare
+    Undeclared { dir: String, name: String },
+}
+
+/// Where the manifest of `dir` is, as shown to the user.
+fn manifest_label(dir: &str) -> String {
+    match dir {
+        "." => "Cargo.toml".to_string(),
+        dir => format!("{}/Cargo.toml", dir),
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingDir(member) => write!(f, "member {} has no directory", member),
+            Self::NotMember(dir) => write!(f, "{} is not a workspace member", dir),
+            Self::Unsorted => write!(f, "workspace members are not sorted"),
+            Self::Duplicate(member) => write!(f, "member {} is listed more than once", member),
+            Self::PackageName { dir, name } => {
+                write!(f, "{} is named {} in its Cargo.toml", dir, name)
+            }
+            Self::DayConstant {
+                dir,
+                found,
+                expected,
+            } => write!(
+                f,
+                "{}/src/main.rs has DAY = {} instead of {}",
+                dir, found, expected
+            ),
+            Self::NotInherited { dir, name } => write!(
+                f,
+                "{} pins {} instead of inheriting it from the workspace",
+                manifest_label(dir),
+                name
+            ),
+            Self::Undeclared { dir, name } => write!(
+                f,
+                "{} inherits {}, which the workspace doesn't declare",
+                manifest_label(dir),
+                name
+            ),
+        }
+    }
+}
+
+/// The day of a directory named `dayNN`.
+fn day_of(name: &str) -> Option<u8> {
+    let digits = name.strip_prefix("day")?;
+    if digits.len() != 2 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// The value of `const DAY: u8 = N;` in `source`, if it has one.
+fn day_constant(source: &str) -> Option<u8> {
+    let start = source.find("const DAY: u8 =")? + "const DAY: u8 =".len();
+    let end = start + source[start..].find(';')?;
+    source[start..end].trim().parse().ok()
+}
+
+fn crate_manifest(root: &Path, dir: &str) -> Result<Option<Document>, Error> {
+    let path = root.join(dir).join("Cargo.toml");
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(&path)?;
+    let manifest = Document::from_str(&text).map_err(|err| Error::Parse(err, dir.to_string()))?;
+    Ok(Some(manifest))
+}
+
+/// Check that the crate in `dir`, `.` for the root package, takes every
+/// dependency the workspace declares from it, and only those.
+fn dependency_problems(
+    root: &Path,
+    dir: &str,
+    manifest: &Document,
+    declared: &[String],
+    problems: &mut Vec<Problem>,
+) {
+    for name in not_inherited(manifest, declared, &root.join(dir), root) {
+        problems.push(Problem::NotInherited {
+            dir: dir.to_string(),
+            name,
+        });
+    }
+    for name in inherited(manifest) {
+        if !declared.contains(&name) {
+            problems.push(Problem::Undeclared {
+                dir: dir.to_string(),
+                name,
+            });
+        }
+    }
+}
+
+/// Cross-check the workspace at `root` against the `dayNN` crates on disk.
+pub fn check(root: &Path) -> Result<Vec<Problem>, Error> {
+    let members = workspace_members(root)?;
+    let mut problems = Vec::new();
+
+    for (i, member) in members.iter().enumerate() {
+        // every problem is reported once, at the member's first repetition
+        match members[..i]
+            .iter()
+            .filter(|earlier| *earlier == member)
+            .count()
+        {
+            0 if !root.join(member).is_dir() => problems.push(Problem::MissingDir(member.clone())),
+            1 => problems.push(Problem::Duplicate(member.clone())),
+            _ => {}
+        }
+    }
+    if members.windows(2).any(|pair| pair[0] > pair[1]) {
+        problems.push(Problem::Unsorted);
+    }
+
+    let root_manifest = load_manifest(root)?;
+    let declared = workspace_dependencies(&root_manifest);
+    dependency_problems(root, ".", &root_manifest, &declared, &mut problems);
+
+    let mut dirs: Vec<String> = std::fs::read_dir(root)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| day_of(name).is_some())
+        .collect();
+    dirs.sort();
+
+    for dir in dirs {
+        let manifest = match crate_manifest(root, &dir)? {
+            Some(manifest) => manifest,
+            None => continue,
+        };
+        if !members.contains(&dir) {
+            problems.push(Problem::NotMember(dir.clone()));
+        }
+        if let Some(name) = get_package_name(&manifest) {
+            if name != dir {
+                problems.push(Problem::PackageName {
+                    dir: dir.clone(),
+                    name: name.to_string(),
+                });
+            }
+        }
+        dependency_problems(root, &dir, &manifest, &declared, &mut problems);
+        let expected = day_of(&dir).unwrap_or_default();
+        if let Ok(source) = std::fs::read_to_string(root.join(&dir).join("src").join("main.rs")) {
+            match day_constant(&source) {
+                Some(found) if found != expected => problems.push(Problem::DayConstant {
+                    dir,
+                    found,
+                    expected,
+                }),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Fix `problems`, as found by `check`, all at once: members without a
+/// directory are dropped, crates that aren't members are added, and the list
+/// is sorted and deduplicated; package names and `DAY` constants are made to
+/// match the directory, and pinned dependencies the workspace declares are
+/// inherited. Undeclared dependencies are left for the user to declare.
+pub fn fix(root: &Path, problems: &[Problem]) -> Result<(), Error> {
+    let mut transaction = Transaction::default();
+
+    let members_problem = problems.iter().any(|problem| {
+        matches!(
+            problem,
+            Problem::MissingDir(_)
+                | Problem::NotMember(_)
+                | Problem::Unsorted
+                | Problem::Duplicate(_)
+        )
+    });
+    if members_problem {
+        let mut manifest = load_manifest(root)?;
+        let mut wanted = workspace_members(root)?;
+        wanted.retain(|member| !problems.contains(&Problem::MissingDir(member.clone())));
+        for problem in problems {
+            if let Problem::NotMember(dir) = problem {
+                wanted.push(dir.clone());
+            }
+        }
+        wanted.sort();
+        wanted.dedup();
+
+        let members = members_mut(&mut manifest)?;
+        members.clear();
+        for member in wanted {
+            members.push(member);
+        }
+        transaction.replace_file(&root.join("Cargo.toml"), &manifest.to_string())?;
+    }
+
+    let mut pinned: Vec<&str> = problems
+        .iter()
+        .filter_map(|problem| match problem {
+            Problem::NotInherited { dir, .. } => Some(dir.as_str()),
+            _ => None,
+        })
+        .collect();
+    pinned.dedup();
+    let declared = workspace_dependencies(&load_manifest(root)?);
+    for dir in pinned {
+        let mut manifest = match crate_manifest(root, dir)? {
+            Some(manifest) => manifest,
+            None => continue,
+        };
+        inherit_declared(&mut manifest, &declared, &root.join(dir), root);
+        transaction.replace_file(&root.join(dir).join("Cargo.toml"), &manifest.to_string())?;
+    }
+
+    for problem in problems {
+        match problem {
+            Problem::PackageName { dir, name } => {
+                let mut manifest = match crate_manifest(root, dir)? {
+                    Some(manifest) => manifest,
+                    None => continue,
+                };
+                manifest["package"]["name"] = toml_edit::value(dir.as_str());
+                transaction
+                    .replace_file(&root.join(dir).join("Cargo.toml"), &manifest.to_string())?;
+
+                // the library is renamed along with the package
+                let main = root.join(dir).join("src").join("main.rs");
+                if main.is_file() {
+                    let source = std::fs::read_to_string(&main)?;
+                    let old = format!("use {}::", name.replace('-', "_"));
+                    if source.contains(&old) {
+                        let source = source.replace(&old, &format!("use {}::", dir));
+                        transaction.replace_file(&main, &source)?;
+                    }
+                }
+            }
+            Problem::DayConstant {
+                dir,
+                found,
+                expected,
+            } => {
+                let main = root.join(dir).join("src").join("main.rs");
+                let source = std::fs::read_to_string(&main)?.replacen(
+                    &format!("const DAY: u8 = {};", found),
+                    &format!("const DAY: u8 = {};", expected),
+                    1,
+                );
+                transaction.replace_file(&main, &source)?;
+            }
+            _ => {}
+        }
+    }
+
+    transaction.commit();
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Workspace(#[from] crate::day::Error),
+    #[error("could not parse {0}/Cargo.toml")]
+    Parse(#[source] toml_edit::TomlError, String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_check_and_fix() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        write(
+            &dir.join("Cargo.toml"),
+            "[package]\nname = \"aoc2021\"\n\n[dependencies]\nthiserror = \"1.0.30\"\n\n[workspace]\nmembers = [\"day02\", \"day01\", \"day02\", \"day04\"]\n\n[workspace.dependencies]\nthiserror = \"1.0.30\"\n",
+        );
+        for day in ["day01", "day02", "day03"] {
+            write(
+                &dir.join(day).join("Cargo.toml"),
+                &format!("[package]\nname = \"{}\"\n", day),
+            );
+            write(
+                &dir.join(day).join("src/main.rs"),
+                &format!(
+                    "use {}::{{part1, part2}};\n\nconst DAY: u8 = {};\n",
+                    day,
+                    &day[3..].trim_start_matches('0')
+                ),
+            );
+        }
+        write(
+            &dir.join("day01/Cargo.toml"),
+            "[package]\nname = \"day-one\"\n",
+        );
+        write(
+            &dir.join("day01/src/main.rs"),
+            "use day_one::{part1, part2};\n\nconst DAY: u8 = 7;\n",
+        );
+        write(
+            &dir.join("day02/Cargo.toml"),
+            "[package]\nname = \"day02\"\n\n[dependencies]\nitertools = { workspace = true }\nthiserror = \"1.0.30\"\n",
+        );
+
+        let problems = check(dir).unwrap();
+        assert_eq!(
+            problems,
+            [
+                Problem::Duplicate("day02".to_string()),
+                Problem::MissingDir("day04".to_string()),
+                Problem::Unsorted,
+                Problem::NotInherited {
+                    dir: ".".to_string(),
+                    name: "thiserror".to_string()
+                },
+                Problem::PackageName {
+                    dir: "day01".to_string(),
+                    name: "day-one".to_string()
+                },
+                Problem::DayConstant {
+                    dir: "day01".to_string(),
+                    found: 7,
+                    expected: 1
+                },
+                Problem::NotInherited {
+                    dir: "day02".to_string(),
+                    name: "thiserror".to_string()
+                },
+                Problem::Undeclared {
+                    dir: "day02".to_string(),
+                    name: "itertools".to_string()
+                },
+                Problem::NotMember("day03".to_string()),
+            ]
+        );
+
+        fix(dir, &problems).unwrap();
+        assert_eq!(
+            check(dir).unwrap(),
+            [Problem::Undeclared {
+                dir: "day02".to_string(),
+                name: "itertools".to_string()
+            }]
+        );
+        let root = std::fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+        assert!(root.contains("thiserror = { workspace = true }"));
+        assert!(root.contains("members = [\"day01\", \"day02\", \"day03\"]"));
+        assert_eq!(workspace_members(dir).unwrap(), ["day01", "day02", "day03"]);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("day01/src/main.rs")).unwrap(),
+            "use day01::{part1, part2};\n\nconst DAY: u8 = 1;\n"
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Tests of solutions against the examples in the puzzle text.
+//!
+//! ```ignore
+//! fn solve1(input: &str) -> Result<u64, Error> { ... }
+//! fn solve2(input: &str) -> Result<u64, Error> { ... }
+//!
+//! #[cfg(test)]
+//! mod test {
+//!     use super::*;
+//!
+//!     aoc2021::example_tests! {
+//!         example0("3,4,3,1,2") {
+//!             part1: solve1 => 5934,
+//!             part2: |input| solve2(input) => 26984457539_u64,
+//!         }
+//!         larger(file "examples/larger.txt") {
+//!             #[ignore]
+//!             part1: solve1 => 0,
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Every example becomes a module with a test for each of its parts, named
+//! like `test::example0::part1`. Inputs are constant string expressions, or
+//! `file` and a path relative to the crate. Answers are anything that converts
+//! into an [`Answer`], so art can be expected as the letters it shows.
+
+use std::fmt::Display;
+
+use crate::{answer::Answer, utils::labelled_diff};
+
+/// Panic unless `actual` is `expected`, showing a diff when either spans
+/// several lines.
+pub fn assert_answer(name: &str, actual: impl Into<Answer>, expected: impl Into<Answer>) {
+    let (actual, expected): (Answer, Answer) = (actual.into(), expected.into());
+    if actual == expected {
+        return;
+    }
+    let (actual_text, expected_text) = (actual.submission(), expected.submission());
+    let (actual, expected) = (actual.to_string(), expected.to_string());
+    if actual.contains('\n') || expected.contains('\n') {
+        panic!(
+            "{}: expected {}, got {}\n{}",
+            name,
+            expected_text,
+            actual_text,
+            labelled_diff("expected", "actual", &(expected + "\n"), &(actual + "\n"))
+        );
+    }
+    panic!("{}: expected {}, got {}", name, expected_text, actual_text);
+}
+
+/// Check what a solution returned for an example; used by [`example_tests!`].
+pub fn check<T, E>(name: &str, result: Result<T, E>, expected: impl Into<Answer>)
+where
+    T: Into<Answer>,
+    E: Display,
+{
+    match result {
+        Ok(actual) => assert_answer(name, actual, expected),
+        Err(err) => panic!("{}: {}", name, err),
+    }
+}
+
+/// Generate `#[test]`s checking solutions against examples; see the
+/// [module documentation](crate::example).
+#[macro_export]
+macro_rules! example_tests {
+    ($(
+        $name:ident($($input:tt)+) {
+            $($(#[$attr:meta])* $part:ident: $solve:expr => $expected:expr),* $(,)?
+        }
+    )*) => {
+        $(
+            mod $name {
+                #[allow(unused_imports)]
+                use super::*;
+
+                const INPUT: &str = $crate::example_input!($($input)+);
+
+                $(
+                    #[test]
+                    $(#[$attr])*
+                    fn $part() {
+                        $crate::example::check(
+                            concat!(stringify!($name), " ", stringify!($part)),
+                            ($solve)(INPUT),
+                            $expected,
+                        );
+                    }
+                )*
+            }
+        )*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! example_input {
+    (file $path:literal) => {
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $path))
+    };
+    ($input:expr) => {
+        $input
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ART: &str = ".##..###.\n#..#.#..#\n#..#.###.\n####.#..#\n#..#.#..#\n#..#.###.";
+
+    fn sum(input: &str) -> Result<u32, std::num::ParseIntError> {
+        input.split(',').map(str::parse::<u32>).sum()
+    }
+
+    fn draw(_input: &str) -> Result<Answer, String> {
+        Ok(Answer::art(ART))
+    }
+
+    crate::example_tests! {
+        numbers("1,2,3") {
+            part1: sum => 6,
+            part2: |input: &str| sum(input).map(|sum| sum * 2) => 12_u64,
+        }
+        letters("") {
+            part1: draw => "AB",
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "numbers part1: expected 7, got 6")]
+    fn test_mismatch() {
+        check("numbers part1", sum("1,2,3"), 7);
+    }
+
+    #[test]
+    #[should_panic(expected = "letters: expected AC, got AB\n--- expected\n+++ actual")]
+    fn test_art_diff() {
+        let expected = ".##...##.\n#..#.#..#\n#..#.#...\n####.#...\n#..#.#..#\n#..#..##.";
+        assert_answer("letters", Answer::art(ART), Answer::art(expected));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid digit")]
+    fn test_error() {
+        check("numbers part1", sum("1,x"), 1);
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+
+use thiserror::Error;
+
+use crate::{
+    config::Config,
+    utils::{get_input, get_puzzle, time_until_unlock},
+};
+
+/// What happened to a single day in a bulk fetch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Fetched,
+    Cached,
+    Locked,
+    Failed(String),
+}
+
+/// Parse a list of days like `1-7`, `3` or `1,4,10-12`.
+pub fn parse_days(spec: &str) -> Result<Vec<u8>, Error> {
+    let invalid = || Error::InvalidDays(spec.to_string());
+    let mut days = Vec::new();
+    for part in spec.split(',').map(str::trim) {
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (first.trim(), last.trim()),
+            None => (part, part),
+        };
+        let first: u8 = first.parse().map_err(|_| invalid())?;
+        let last: u8 = last.parse().map_err(|_| invalid())?;
+        if first == 0 || last > 25 || first > last {
+            return Err(invalid());
+        }
+        days.extend(first..=last);
+    }
+    days.sort_unstable();
+    days.dedup();
+    Ok(days)
+}
+
+/// Fetch the input, and optionally the puzzle page, for `day` unless they are
+/// already on disk.
+pub fn fetch_day(config: &Config, day: u8, puzzle: bool) -> Outcome {
+    let have_input = config.input_for(day).exists();
+    let have_puzzle = !puzzle || config.puzzle_for(day).exists();
+    if have_input && have_puzzle {
+        return Outcome::Cached;
+    }
+    if time_until_unlock(day).is_some() {
+        return Outcome::Locked;
+    }
+
+    let result = get_input(config, day).and_then(|_| match have_puzzle {
+        true => Ok(()),
+        false => get_puzzle(config, day),
+    });
+    match result {
+        Ok(()) => Outcome::Fetched,
+        Err(err) => Outcome::Failed(err.to_string()),
+    }
+}
+
+/// Fetch every day in `days` using at most `jobs` concurrent requests. The
+/// outcomes are returned in the order of `days`.
+pub fn fetch_days(config: &Config, days: &[u8], puzzle: bool, jobs: usize) -> Vec<(u8, Outcome)> {
+    let next = AtomicUsize::new(0);
+    let outcomes = Mutex::new(Vec::with_capacity(days.len()));
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, days.len().max(1)) {
+            scope.spawn(|| {
+                while let Some(&day) = days.get(next.fetch_add(1, Ordering::SeqCst)) {
+                    let outcome = fetch_day(config, day, puzzle);
+                    outcomes
+                        .lock()
+                        .expect("fetch worker panicked")
+                        .push((day, outcome));
+                }
+            });
+        }
+    });
+
+    let mut outcomes = outcomes.into_inner().expect("fetch worker panicked");
+    outcomes.sort_by_key(|&(day, _)| days.iter().position(|&d| d == day));
+    outcomes
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid list of days: {0}, expected something like 1-7 or 1,3,5")]
+    InvalidDays(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_days() {
+        assert_eq!(parse_days("1-7").unwrap(), (1..=7).collect::<Vec<_>>());
+        assert_eq!(parse_days("3").unwrap(), [3]);
+        assert_eq!(parse_days("10-12, 1,11").unwrap(), [1, 10, 11, 12]);
+        assert!(parse_days("0-3").is_err());
+        assert!(parse_days("7-1").is_err());
+        assert!(parse_days("24-26").is_err());
+        assert!(parse_days("one").is_err());
+    }
+}
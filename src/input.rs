@@ -47,7 +47,7 @@ where
             if buf.is_empty() {
                 None
             } else {
-                match T::from_str(&buf.trim()) {
+                match T::from_str(buf.trim()) {
                     Ok(t) => Some(t),
                     Err(e) => {
                         eprintln!("{}:{}: {} for {:?}", file_name, line, e, buf);
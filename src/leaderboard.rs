@@ -0,0 +1,266 @@
+use std::{collections::BTreeMap, fmt::Write, time::Duration};
+
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+
+use crate::{
+    cache::Resource,
+    config::Config,
+    utils::{fetch, format_duration, validate_json},
+};
+
+pub fn url_for(id: &str) -> String {
+    format!(
+        "https://adventofcode.com/{}/leaderboard/private/view/{}.json",
+        2021, id
+    )
+}
+
+/// The site sends ids and timestamps sometimes as numbers, sometimes as strings.
+fn number_or_string<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Number(u64),
+        String(String),
+    }
+
+    match Value::deserialize(deserializer)? {
+        Value::Number(n) => Ok(n),
+        Value::String(s) => s.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Leaderboard {
+    #[serde(deserialize_with = "number_or_string")]
+    pub owner_id: u64,
+    pub event: String,
+    pub members: BTreeMap<String, Member>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Member {
+    #[serde(deserialize_with = "number_or_string")]
+    pub id: u64,
+    pub name: Option<String>,
+    pub stars: u32,
+    pub local_score: u32,
+    #[serde(default)]
+    pub global_score: u32,
+    #[serde(deserialize_with = "number_or_string")]
+    pub last_star_ts: u64,
+    /// Day -> part -> star
+    #[serde(default)]
+    pub completion_day_level: BTreeMap<u8, BTreeMap<u8, Star>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Star {
+    #[serde(deserialize_with = "number_or_string")]
+    pub get_star_ts: u64,
+}
+
+impl Member {
+    pub fn display_name(&self) -> String {
+        match self.name {
+            Some(ref name) => name.clone(),
+            None => format!("(anonymous user #{})", self.id),
+        }
+    }
+
+    pub fn stars_on(&self, day: u8) -> usize {
+        self.completion_day_level
+            .get(&day)
+            .map(BTreeMap::len)
+            .unwrap_or_default()
+    }
+
+    /// Time between getting the first and the second star on `day`.
+    pub fn part2_delta(&self, day: u8) -> Option<Duration> {
+        let parts = self.completion_day_level.get(&day)?;
+        let (part1, part2) = (parts.get(&1)?, parts.get(&2)?);
+        Some(Duration::from_secs(
+            part2.get_star_ts.saturating_sub(part1.get_star_ts),
+        ))
+    }
+}
+
+impl Leaderboard {
+    pub fn from_json(json: &[u8]) -> Result<Self, Error> {
+        serde_json::from_slice(json).map_err(Into::into)
+    }
+
+    /// Members ordered by local score, best first.
+    pub fn ranked(&self) -> Vec<&Member> {
+        let mut members: Vec<&Member> = self.members.values().collect();
+        members.sort_by(|a, b| {
+            b.local_score
+                .cmp(&a.local_score)
+                .then(b.stars.cmp(&a.stars))
+                .then(a.last_star_ts.cmp(&b.last_star_ts))
+        });
+        members
+    }
+
+    /// The last day anyone has a star on.
+    pub fn last_day(&self) -> u8 {
+        self.members
+            .values()
+            .filter_map(|m| m.completion_day_level.keys().max())
+            .max()
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// A table of members with their score and stars per day, followed by the
+    /// time each member took from part 1 to part 2.
+    pub fn render(&self) -> String {
+        let members = self.ranked();
+        let days = 1..=self.last_day();
+        let name_width = members
+            .iter()
+            .map(|m| m.display_name().chars().count())
+            .max()
+            .unwrap_or_default()
+            .max(4);
+
+        let mut out = String::new();
+        let _ = write!(out, "{:>4} {:>5}  ", "", "score");
+        for day in days.clone() {
+            let _ = write!(out, "{}", day % 10);
+        }
+        let _ = writeln!(out, "  name");
+        for (rank, member) in members.iter().enumerate() {
+            let _ = write!(out, "{:>3}) {:>5}  ", rank + 1, member.local_score);
+            for day in days.clone() {
+                out.push(match member.stars_on(day) {
+                    0 => '.',
+                    1 => '+',
+                    _ => '*',
+                });
+            }
+            let _ = writeln!(out, "  {}", member.display_name());
+        }
+
+        let headers: Vec<String> = days.clone().map(|day| format!("day {}", day)).collect();
+        let deltas: Vec<Vec<String>> = members
+            .iter()
+            .map(|member| {
+                days.clone()
+                    .map(|day| {
+                        member
+                            .part2_delta(day)
+                            .map(format_duration)
+                            .unwrap_or_else(|| "-".to_string())
+                    })
+                    .collect()
+            })
+            .collect();
+        // deltas over a day, like `2d 00:00:05`, are wider than the rest
+        let delta_width = headers
+            .iter()
+            .chain(deltas.iter().flatten())
+            .map(|cell| cell.chars().count())
+            .max()
+            .unwrap_or_default()
+            .max(8);
+
+        let _ = write!(out, "\npart 2 deltas\n{:name_width$}", "name");
+        for header in &headers {
+            let _ = write!(out, " {:>delta_width$}", header);
+        }
+        out.push('\n');
+        for (member, deltas) in members.iter().zip(&deltas) {
+            let _ = write!(out, "{:name_width$}", member.display_name());
+            for delta in deltas {
+                let _ = write!(out, " {:>delta_width$}", delta);
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Fetch a private leaderboard, at most once every 15 minutes as the site asks.
+pub fn get_leaderboard(config: &Config, id: &str) -> Result<Leaderboard, Error> {
+    let body = fetch(config, &url_for(id), Resource::Leaderboard, validate_json)?;
+    Leaderboard::from_json(&body)
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Fetch(#[from] crate::utils::Error),
+    #[error("could not parse leaderboard")]
+    Parse(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../fixtures/leaderboard.json");
+
+    #[test]
+    fn test_parse_fixture() {
+        let leaderboard = Leaderboard::from_json(FIXTURE.as_bytes()).unwrap();
+        assert_eq!(leaderboard.owner_id, 152345);
+        assert_eq!(leaderboard.members.len(), 3);
+        assert_eq!(leaderboard.last_day(), 3);
+
+        let ranked = leaderboard.ranked();
+        assert_eq!(ranked[0].display_name(), "Lisa");
+        assert_eq!(ranked[1].display_name(), "Anders Quist");
+        assert_eq!(ranked[2].display_name(), "(anonymous user #301777)");
+        assert_eq!(ranked[2].last_star_ts, 0);
+
+        assert_eq!(ranked[1].stars_on(3), 1);
+        assert_eq!(ranked[1].part2_delta(1), Some(Duration::from_secs(120)));
+        assert_eq!(ranked[1].part2_delta(3), None);
+        assert_eq!(ranked[0].part2_delta(3), Some(Duration::from_secs(3900)));
+    }
+
+    #[test]
+    fn test_render() {
+        let leaderboard = Leaderboard::from_json(FIXTURE.as_bytes()).unwrap();
+        let rendered = leaderboard.render();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0], "     score  123  name");
+        assert_eq!(lines[1], "  1)    16  ***  Lisa");
+        assert_eq!(lines[2], "  2)    12  **+  Anders Quist");
+        assert_eq!(lines[3], "  3)     0  ...  (anonymous user #301777)");
+        assert!(rendered.contains(&format!("{:24} 00:01:00 00:01:40 01:05:00", "Lisa")));
+    }
+
+    #[test]
+    fn test_render_long_delta() {
+        // Anders solves part 2 of day 2 two days and five seconds after part 1
+        let fixture = FIXTURE.replace("1638422040", &(1638421800 + 2 * 86400 + 5).to_string());
+        let leaderboard = Leaderboard::from_json(fixture.as_bytes()).unwrap();
+        let rendered = leaderboard.render();
+        let deltas: Vec<&str> = rendered
+            .lines()
+            .skip_while(|line| *line != "part 2 deltas")
+            .skip(1)
+            .collect();
+        assert_eq!(
+            deltas[0],
+            format!(
+                "{:24} {:>11} {:>11} {:>11}",
+                "name", "day 1", "day 2", "day 3"
+            )
+        );
+        assert_eq!(
+            deltas[2],
+            format!(
+                "{:24} {:>11} {:>11} {:>11}",
+                "Anders Quist", "00:02:00", "2d 00:00:05", "-"
+            )
+        );
+        assert!(deltas.iter().all(|line| line.len() == deltas[0].len()));
+    }
+}
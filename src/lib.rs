@@ -1,6 +1,23 @@
+pub mod allocations;
+pub mod answer;
+pub mod cache;
 pub mod config;
 pub mod day;
+pub mod deps;
+pub mod doctor;
+pub mod example;
+pub mod fetch;
 pub mod input;
+pub mod leaderboard;
+pub mod ocr;
+pub mod puzzle;
+pub mod stats;
+pub mod status;
+pub mod sync;
+pub mod template;
+pub mod timing;
+mod transaction;
 pub mod utils;
+pub mod verify;
 
 pub use input::parse;
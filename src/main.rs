@@ -1,4 +1,7 @@
-use aoc2021::config::Config;
+use aoc2021::{
+    cache::Cache,
+    config::{Config, OFFLINE_ENV},
+};
 use color_eyre::eyre::{bail, Result};
 use path_absolutize::Absolutize;
 use std::path::PathBuf;
@@ -19,6 +22,30 @@ enum ConfigOpt {
         /// Path to input files
         #[structopt(short, long, parse(from_os_str))]
         inputs: Option<PathBuf>,
+
+        /// Contact information sent with every request, e.g. an email address
+        #[structopt(short, long)]
+        contact: Option<String>,
+
+        /// Author named in generated crates, empty to unset
+        #[structopt(short, long)]
+        author: Option<String>,
+
+        /// Stay offline by default (true or false)
+        #[structopt(long)]
+        offline_mode: Option<bool>,
+
+        /// HTTP(S) proxy url, empty to unset
+        #[structopt(long)]
+        proxy: Option<String>,
+
+        /// Comma separated hosts that bypass the proxy
+        #[structopt(long)]
+        no_proxy: Option<String>,
+
+        /// Extra PEM root certificate file, may be repeated; empty to unset
+        #[structopt(long, parse(from_os_str))]
+        ca_cert: Vec<PathBuf>,
     },
 }
 
@@ -30,7 +57,16 @@ impl ConfigOpt {
                 let content = std::fs::read_to_string(aoc2021::config::path())?;
                 println!("{}", content);
             }
-            Self::Set { session, inputs } => {
+            Self::Set {
+                session,
+                inputs,
+                contact,
+                author,
+                offline_mode,
+                proxy,
+                no_proxy,
+                ca_cert,
+            } => {
                 let mut config = Config::load().unwrap_or_default();
                 if let Some(session) = session {
                     if session.is_empty() {
@@ -44,6 +80,41 @@ impl ConfigOpt {
                     }
                     config.input_files = Some(inputs.absolutize()?.into_owned());
                 }
+                if let Some(contact) = contact {
+                    if contact.trim().is_empty() {
+                        bail!("contact can't be empty")
+                    }
+                    config.contact = Some(contact);
+                }
+                if let Some(author) = author {
+                    config.author = Some(author).filter(|author| !author.is_empty());
+                }
+                if let Some(offline_mode) = offline_mode {
+                    config.offline = offline_mode;
+                }
+                if let Some(proxy) = proxy {
+                    config.proxy = Some(proxy).filter(|proxy| !proxy.is_empty());
+                }
+                if let Some(no_proxy) = no_proxy {
+                    config.no_proxy = no_proxy
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|host| !host.is_empty())
+                        .map(str::to_string)
+                        .collect();
+                }
+                if !ca_cert.is_empty() {
+                    config.ca_certs = Vec::new();
+                    for path in ca_cert
+                        .into_iter()
+                        .filter(|path| !path.as_os_str().is_empty())
+                    {
+                        if !path.is_file() {
+                            bail!("CA certificate {} is not a file", path.display())
+                        }
+                        config.ca_certs.push(path.absolutize()?.into_owned());
+                    }
+                }
                 config.save()?;
             }
         }
@@ -51,14 +122,116 @@ impl ConfigOpt {
     }
 }
 
+#[derive(StructOpt, Debug)]
+enum CacheOpt {
+    /// Print path to the response cache
+    Path,
+    /// Remove every cached response
+    Clear,
+}
+
+impl CacheOpt {
+    fn run(self) -> Result<()> {
+        let cache = Cache::default();
+        match self {
+            Self::Path => println!("{}", cache.dir().display()),
+            Self::Clear => {
+                let count = cache.clear()?;
+                println!("removed {} cached responses", count);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(StructOpt, Debug)]
+enum TemplateOpt {
+    /// Update generated files of existing days that haven't been edited by hand
+    Sync {
+        /// Only report what would change
+        #[structopt(long, short = "n")]
+        dry_run: bool,
+        /// Replace a file no template record covers by the template's render,
+        /// given as reported, e.g. day01/src/main.rs
+        #[structopt(long, parse(from_os_str))]
+        adopt: Vec<PathBuf>,
+    },
+}
+
+impl TemplateOpt {
+    fn run(self) -> Result<()> {
+        use aoc2021::sync::Outcome;
+
+        match self {
+            Self::Sync { dry_run, adopt } => {
+                let config = Config::load()?;
+                let root = aoc2021::day::workspace_root(&std::env::current_dir()?)?;
+                let synced = aoc2021::sync::sync(&config, &root, dry_run, &adopt)?;
+
+                let (mut updated, mut edited) = (0, 0);
+                for file in synced.iter() {
+                    match file.outcome {
+                        Outcome::UpToDate => {}
+                        Outcome::Updated => {
+                            updated += 1;
+                            println!("updated {}", file.path.display());
+                        }
+                        Outcome::Edited(ref diff) => {
+                            edited += 1;
+                            println!("{} was edited, left alone", file.path.display());
+                            print!("{}", diff);
+                        }
+                        Outcome::Unrecorded(ref diff) => {
+                            edited += 1;
+                            println!(
+                                "{} has no template record, left alone; --adopt {} replaces it by the render",
+                                file.path.display(),
+                                file.path.display()
+                            );
+                            print!("{}", diff);
+                        }
+                        Outcome::Adopted => {
+                            updated += 1;
+                            println!("adopted {}", file.path.display());
+                        }
+                        Outcome::Missing => println!("{} is missing", file.path.display()),
+                    }
+                }
+                println!("{} updated, {} left alone", updated, edited);
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(StructOpt, Debug)]
 #[structopt(about = "advent of code 2021")]
+struct Opt {
+    /// Never touch the network, only use cached inputs and pages
+    #[structopt(long, global = true)]
+    offline: bool,
+
+    #[structopt(subcommand)]
+    cmd: Command,
+}
+
+#[derive(StructOpt, Debug)]
 enum Command {
     /// Handle configuration
     Config {
         #[structopt(subcommand)]
         cmd: ConfigOpt,
     },
+    /// Handle the HTTP response cache
+    Cache {
+        #[structopt(subcommand)]
+        cmd: CacheOpt,
+    },
+    /// Work with the template sets days are generated from
+    Template {
+        #[structopt(subcommand)]
+        cmd: TemplateOpt,
+    },
     /// Emit the URL to a specified puzzle
     Url {
         #[structopt(long, default_value = "1")]
@@ -70,6 +243,83 @@ enum Command {
         /// Force overwrite files
         #[structopt(long, short)]
         force: bool,
+        /// Print what would change as diffs without writing anything
+        #[structopt(long, short = "n")]
+        dry_run: bool,
+        /// Template set to generate the crate from, e.g. plain, grid or single-line-csv
+        #[structopt(long, short, default_value = aoc2021::template::DEFAULT)]
+        template: String,
+    },
+    /// Delete a day's crate and drop it from the workspace
+    Remove {
+        #[structopt(long)]
+        day: u8,
+        /// Don't ask for confirmation
+        #[structopt(long, short)]
+        yes: bool,
+    },
+    /// Move a day's crate to .archive/ and drop it from the workspace
+    Archive {
+        #[structopt(long)]
+        day: u8,
+        /// Don't ask for confirmation
+        #[structopt(long, short)]
+        yes: bool,
+    },
+    /// Pin every day's dependencies once, in [workspace.dependencies]
+    SyncDeps {
+        /// Only print what would change
+        #[structopt(long, short = "n")]
+        dry_run: bool,
+    },
+    /// Check that workspace members and day crates agree
+    Doctor {
+        /// Fix what can be fixed
+        #[structopt(long)]
+        fix: bool,
+    },
+    /// Count down to a puzzle's unlock, then fetch its input and puzzle page
+    Wait {
+        /// Defaults to the next day that is still locked
+        #[structopt(long)]
+        day: Option<u8>,
+    },
+    /// Download inputs for several days at once, skipping cached ones
+    Fetch {
+        /// Every unlocked day
+        #[structopt(long, required_unless = "days", conflicts_with = "days")]
+        all: bool,
+        /// Days to fetch, e.g. 1-7 or 1,3,5
+        #[structopt(long)]
+        days: Option<String>,
+        /// Fetch the puzzle page as well
+        #[structopt(long)]
+        puzzle: bool,
+        /// Maximum number of concurrent requests
+        #[structopt(long, short, default_value = "4")]
+        jobs: usize,
+    },
+    /// Show a private leaderboard
+    Leaderboard {
+        /// Leaderboard id, the number at the end of its url
+        id: String,
+    },
+    /// Show your personal leaderboard times
+    Stats {
+        /// Print as JSON instead of a table
+        #[structopt(long)]
+        json: bool,
+    },
+    /// Show local progress for every day
+    Status,
+    /// Run every day's solution and compare it with the expected answers
+    Verify {
+        /// Days to verify, e.g. 1-7 or 1,3,5; every day by default
+        #[structopt(long)]
+        days: Option<String>,
+        /// Write down results for parts without an expected answer
+        #[structopt(long)]
+        record: bool,
     },
 }
 
@@ -77,20 +327,237 @@ impl Command {
     fn run(self) -> Result<()> {
         match self {
             Self::Config { cmd } => cmd.run()?,
+            Self::Cache { cmd } => cmd.run()?,
+            Self::Template { cmd } => cmd.run()?,
             Self::Url { day } => {
                 println!("{}", aoc2021::utils::url_for_day(day));
             }
-            Self::Init { day, force } => {
+            Self::Init {
+                day,
+                force,
+                dry_run,
+                template,
+            } => {
+                let config = Config::load()?;
+                aoc2021::day::initialize(&config, day, force, dry_run, &template)?;
+            }
+            Self::Remove { day, yes } => {
+                if !yes && !confirm(&format!("delete the crate for day {}?", day))? {
+                    bail!("aborted")
+                }
+                aoc2021::day::remove(day, false)?;
+            }
+            Self::Archive { day, yes } => {
+                if !yes && !confirm(&format!("archive the crate for day {}?", day))? {
+                    bail!("aborted")
+                }
+                if let Some(archived) = aoc2021::day::remove(day, true)? {
+                    println!("{}", archived.display());
+                }
+            }
+            Self::SyncDeps { dry_run } => {
+                let root = aoc2021::day::workspace_root(&std::env::current_dir()?)?;
+                let report = aoc2021::deps::sync_deps(&root, dry_run)?;
+                for conflict in report.conflicts.iter() {
+                    match conflict.chosen {
+                        Some(ref chosen) => println!(
+                            "{} was pinned as {}, using {}",
+                            conflict.name,
+                            conflict.specs.join(", "),
+                            chosen
+                        ),
+                        None => println!(
+                            "{} was pinned as {}, left alone",
+                            conflict.name,
+                            conflict.specs.join(", ")
+                        ),
+                    }
+                }
+                for (_, diff) in report.changed.iter() {
+                    print!("{}", diff);
+                }
+            }
+            Self::Doctor { fix } => {
+                let root = aoc2021::day::workspace_root(&std::env::current_dir()?)?;
+                let problems = aoc2021::doctor::check(&root)?;
+                for problem in problems.iter() {
+                    println!("{}", problem);
+                }
+                if fix {
+                    aoc2021::doctor::fix(&root, &problems)?;
+                    let remaining = aoc2021::doctor::check(&root)?;
+                    println!(
+                        "fixed {} problems",
+                        problems.len().saturating_sub(remaining.len())
+                    );
+                    for problem in remaining.iter() {
+                        println!("still: {}", problem);
+                    }
+                    if !remaining.is_empty() {
+                        bail!("{} problems remain", remaining.len())
+                    }
+                } else if !problems.is_empty() {
+                    bail!(
+                        "{} problems found, run with --fix to fix them",
+                        problems.len()
+                    )
+                }
+            }
+            Self::Wait { day } => {
+                use aoc2021::utils::{
+                    check_day, format_duration, next_locked_day, time_until_unlock, with_retries,
+                };
+                use std::io::Write;
+
+                let day = match day {
+                    Some(day) => day,
+                    None => match next_locked_day() {
+                        Some(day) => day,
+                        None => bail!("every day has unlocked, pass --day"),
+                    },
+                };
+                check_day(day)?;
+                let config = Config::load()?;
+                while let Some(remaining) = time_until_unlock(day) {
+                    print!("\rday {} unlocks in {}  ", day, format_duration(remaining));
+                    std::io::stdout().flush()?;
+                    std::thread::sleep(remaining.min(std::time::Duration::from_secs(1)));
+                }
+                println!("\rday {} is unlocked        ", day);
+
+                with_retries(5, || aoc2021::utils::get_input(&config, day))?;
+                println!("{}", config.input_for(day).display());
+                with_retries(5, || aoc2021::utils::get_puzzle(&config, day))?;
+                println!("{}", config.puzzle_for(day).display());
+            }
+            Self::Fetch {
+                all,
+                days,
+                puzzle,
+                jobs,
+            } => {
+                use aoc2021::fetch::{fetch_days, parse_days, Outcome};
+
+                let config = Config::load()?;
+                let days = match days {
+                    Some(days) if !all => parse_days(&days)?,
+                    _ => (1..=25).collect(),
+                };
+                let outcomes = fetch_days(&config, &days, puzzle, jobs);
+
+                let (mut fetched, mut cached, mut locked, mut failed) = (0, 0, 0, 0);
+                for (day, outcome) in outcomes.iter() {
+                    match outcome {
+                        Outcome::Fetched => fetched += 1,
+                        Outcome::Cached => cached += 1,
+                        Outcome::Locked => locked += 1,
+                        Outcome::Failed(err) => {
+                            failed += 1;
+                            eprintln!("day {:02}: {}", day, err);
+                        }
+                    }
+                }
+                println!(
+                    "{} fetched, {} cached, {} locked, {} failed",
+                    fetched, cached, locked, failed
+                );
+                if failed > 0 {
+                    bail!("failed to fetch {} days", failed)
+                }
+            }
+            Self::Leaderboard { id } => {
                 let config = Config::load()?;
-                aoc2021::day::initialize(&config, day, force)?;
+                let leaderboard = aoc2021::leaderboard::get_leaderboard(&config, &id)?;
+                print!("{}", leaderboard.render());
+            }
+            Self::Stats { json } => {
+                let config = Config::load()?;
+                let entries = aoc2021::stats::get_stats(&config)?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&entries)?);
+                } else {
+                    print!("{}", aoc2021::stats::render(&entries));
+                }
+            }
+            Self::Status => {
+                let root = aoc2021::day::workspace_root(&std::env::current_dir()?)?;
+                let config = Config::load()?.in_workspace(&root);
+                let days = aoc2021::status::status(&root, &config)?;
+                print!("{}", aoc2021::status::render(&days));
+            }
+            Self::Verify { days, record } => {
+                use aoc2021::verify::{verify, Check};
+
+                let root = aoc2021::day::workspace_root(&std::env::current_dir()?)?;
+                let config = Config::load()?.in_workspace(&root);
+                let days = match days {
+                    Some(days) => aoc2021::fetch::parse_days(&days)?,
+                    None => (1..=25).collect(),
+                };
+                let answers_path = config.answers_path();
+                let checks = verify(&root, &answers_path, &days, record)?;
+
+                let mut drifted = 0;
+                for check in checks.iter() {
+                    let label = format!("day {:02} part {}", check.day, check.part);
+                    match check.check {
+                        Check::Match => println!("{}: ok", label),
+                        Check::Mismatch {
+                            ref expected,
+                            ref actual,
+                        } => {
+                            drifted += 1;
+                            println!("{}: expected {}, got {}", label, expected, actual);
+                        }
+                        Check::Unknown {
+                            ref actual,
+                            recorded,
+                        } => println!(
+                            "{}: {} ({})",
+                            label,
+                            actual,
+                            if recorded {
+                                "recorded"
+                            } else {
+                                "no expected answer"
+                            }
+                        ),
+                        Check::Failed(ref reason) => {
+                            drifted += 1;
+                            println!("{}: failed, {}", label, reason);
+                        }
+                    }
+                }
+                if drifted > 0 {
+                    bail!(
+                        "{} of {} parts drifted from {}",
+                        drifted,
+                        checks.len(),
+                        answers_path.display()
+                    )
+                }
             }
         }
         Ok(())
     }
 }
 
+/// Ask a yes/no question on stdin, defaulting to no.
+fn confirm(question: &str) -> Result<bool> {
+    use std::io::Write;
+
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
 fn main() -> Result<()> {
     color_eyre::install()?;
-    let command = Command::from_args();
-    command.run()
+    let opt = Opt::from_args();
+    if opt.offline {
+        std::env::set_var(OFFLINE_ENV, "1");
+    }
+    opt.cmd.run()
 }
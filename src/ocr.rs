@@ -0,0 +1,273 @@
+use thiserror::Error;
+
+/// The 4×6 font most letter answers are drawn in. Letters vary in width, `I`
+/// and `Y` being narrower and wider than the rest.
+const SMALL: &[(char, [&str; 6])] = &[
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', ["###", ".#.", ".#.", ".#.", ".#.", "###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// The 6×10 font of the larger letter answers.
+const LARGE: &[(char, [&str; 10])] = &[
+    (
+        'A',
+        [
+            "..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'B',
+        [
+            "#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#",
+            "#....#", "#####.",
+        ],
+    ),
+    (
+        'C',
+        [
+            ".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+            "#....#", ".####.",
+        ],
+    ),
+    (
+        'E',
+        [
+            "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+            "#.....", "######",
+        ],
+    ),
+    (
+        'F',
+        [
+            "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+            "#.....", "#.....",
+        ],
+    ),
+    (
+        'G',
+        [
+            ".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#",
+            "#...##", ".###.#",
+        ],
+    ),
+    (
+        'H',
+        [
+            "#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'J',
+        [
+            "...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.",
+            "#...#.", ".###..",
+        ],
+    ),
+    (
+        'K',
+        [
+            "#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..",
+            "#...#.", "#....#",
+        ],
+    ),
+    (
+        'L',
+        [
+            "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+            "#.....", "######",
+        ],
+    ),
+    (
+        'N',
+        [
+            "#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##",
+            "#...##", "#....#",
+        ],
+    ),
+    (
+        'P',
+        [
+            "#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....",
+            "#.....", "#.....",
+        ],
+    ),
+    (
+        'R',
+        [
+            "#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'X',
+        [
+            "#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'Z',
+        [
+            "######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....",
+            "#.....", "######",
+        ],
+    ),
+];
+
+fn is_lit(c: char) -> bool {
+    matches!(c, '#' | '█')
+}
+
+/// Look a glyph, one string of `#` and `.` per row, up in `font`.
+fn lookup<const N: usize>(font: &[(char, [&str; N])], glyph: &[String]) -> Option<char> {
+    font.iter()
+        .find(|(_, rows)| rows.iter().zip(glyph).all(|(row, glyph)| row == glyph))
+        .map(|(letter, _)| *letter)
+}
+
+/// Read the letters drawn in `art`, lit pixels being `#` or `█` and anything
+/// else dark. Letters are told apart by the blank columns between them and the
+/// font by the height of the drawing.
+pub fn recognize(art: &str) -> Result<String, Error> {
+    let mut rows: Vec<Vec<bool>> = art
+        .lines()
+        .map(|line| line.chars().map(is_lit).collect())
+        .collect();
+    while rows.last().is_some_and(|row| !row.contains(&true)) {
+        rows.pop();
+    }
+    let first = rows.iter().position(|row| row.contains(&true)).unwrap_or(0);
+    rows.drain(..first);
+    if rows.is_empty() {
+        return Err(Error::Empty);
+    }
+    let width = rows.iter().map(Vec::len).max().unwrap_or_default();
+    for row in rows.iter_mut() {
+        row.resize(width, false);
+    }
+    let lit_column = |x: usize| rows.iter().any(|row| row[x]);
+
+    let mut letters = String::new();
+    let mut x = 0;
+    while x < width {
+        if !lit_column(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < width && lit_column(x) {
+            x += 1;
+        }
+        let glyph: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                row[start..x]
+                    .iter()
+                    .map(|&lit| if lit { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        let letter = match rows.len() {
+            6 => lookup(SMALL, &glyph),
+            10 => lookup(LARGE, &glyph),
+            height => return Err(Error::Height(height)),
+        };
+        letters.push(letter.ok_or_else(|| Error::UnknownGlyph(glyph.join("\n")))?);
+    }
+    Ok(letters)
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("nothing is drawn")]
+    Empty,
+    #[error("letters are {0} rows high, expected 6 or 10")]
+    Height(usize),
+    #[error("unknown letter:\n{0}")]
+    UnknownGlyph(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_small() {
+        let art = "\
+#..#.###...##..####.###..#..#.####.###.
+#..#.#..#.#..#.#....#..#.#..#.#....#..#
+####.###..#....###..#..#.####.###..#..#
+#..#.#..#.#....#....###..#..#.#....###.
+#..#.#..#.#..#.#....#.#..#..#.#....#.#.
+#..#.###...##..####.#..#.#..#.####.#..#
+";
+        assert_eq!(recognize(art).unwrap(), "HBCERHER");
+
+        let blocks = art.replace('#', "█").replace('.', " ");
+        assert_eq!(recognize(&format!("\n{}\n", blocks)).unwrap(), "HBCERHER");
+    }
+
+    #[test]
+    fn test_large() {
+        let art = "\
+#....#..######
+#....#..#.....
+.#..#...#.....
+.#..#...#.....
+..##....#####.
+..##....#.....
+.#..#...#.....
+.#..#...#.....
+#....#..#.....
+#....#..######
+";
+        assert_eq!(recognize(art).unwrap(), "XE");
+    }
+
+    /// Every letter of a font drawn side by side, two columns apart.
+    fn draw<const N: usize>(font: &[(char, [&str; N])]) -> String {
+        (0..N)
+            .map(|y| {
+                font.iter()
+                    .map(|(_, rows)| rows[y])
+                    .collect::<Vec<_>>()
+                    .join("..")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_alphabets() {
+        assert_eq!(recognize(&draw(SMALL)).unwrap(), "ABCEFGHIJKLOPRSUYZ");
+        assert_eq!(recognize(&draw(LARGE)).unwrap(), "ABCEFGHJKLNPRXZ");
+    }
+
+    #[test]
+    fn test_unknown() {
+        assert!(matches!(
+            recognize("#.#\n.#.\n#.#\n.#.\n#.#\n.#."),
+            Err(Error::UnknownGlyph(_))
+        ));
+        assert!(matches!(recognize("#\n#\n#"), Err(Error::Height(3))));
+        assert!(matches!(recognize("...\n"), Err(Error::Empty)));
+    }
+}
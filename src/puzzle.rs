@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use crate::utils::html_to_text;
+
+/// What can be read from a fetched puzzle page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Puzzle {
+    /// The title without the `--- Day N: ` decoration
+    pub title: Option<String>,
+    /// The contents of every `<pre><code>` block, in order
+    pub examples: Vec<String>,
+}
+
+impl Puzzle {
+    pub fn parse(html: &str) -> Self {
+        Self {
+            title: title(html),
+            examples: examples(html),
+        }
+    }
+
+    /// Parse the page saved at `path`, if it has been fetched.
+    pub fn load(path: &Path) -> std::io::Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(Self::parse(&std::fs::read_to_string(path)?)))
+    }
+}
+
+fn title(html: &str) -> Option<String> {
+    let start = html.find("<h2>")? + "<h2>".len();
+    let end = start + html[start..].find("</h2>")?;
+    let heading = html_to_text(&html[start..end]);
+    let heading = heading.trim().trim_matches('-').trim();
+    let title = match heading.split_once(": ") {
+        Some((_, title)) => title,
+        None => heading,
+    };
+    Some(title.to_string()).filter(|title| !title.is_empty())
+}
+
+fn examples(html: &str) -> Vec<String> {
+    const OPEN: &str = "<pre><code>";
+    const CLOSE: &str = "</code></pre>";
+
+    let mut examples = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find(OPEN) {
+        rest = &rest[start + OPEN.len()..];
+        let end = match rest.find(CLOSE) {
+            Some(end) => end,
+            None => break,
+        };
+        examples.push(html_to_text(&rest[..end]).trim_end().to_string());
+        rest = &rest[end + CLOSE.len()..];
+    }
+    examples
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../fixtures/puzzle.html");
+
+    #[test]
+    fn test_parse_fixture() {
+        let puzzle = Puzzle::parse(FIXTURE);
+        assert_eq!(puzzle.title.as_deref(), Some("Sonar Sweep"));
+        assert_eq!(puzzle.examples.len(), 2);
+        assert_eq!(
+            puzzle.examples[0],
+            "199\n200\n208\n210\n200\n207\n240\n269\n260\n263"
+        );
+        assert!(puzzle.examples[1].starts_with("199  A"));
+    }
+}
@@ -0,0 +1,174 @@
+use std::{fmt::Write, time::Duration};
+
+use serde::{Serialize, Serializer};
+use thiserror::Error;
+
+use crate::{
+    cache::{Cache, Resource},
+    config::Config,
+    utils::{fetch, format_duration, html_to_text, validate_page},
+};
+
+pub fn url() -> String {
+    format!("https://adventofcode.com/{}/leaderboard/self", 2021)
+}
+
+/// One star on the personal leaderboard times page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Entry {
+    pub day: u8,
+    pub part: u8,
+    /// Time from unlock to the star, `None` if it took more than 24 hours
+    #[serde(serialize_with = "as_seconds")]
+    pub time: Option<Duration>,
+    pub rank: u32,
+    pub score: u32,
+}
+
+fn as_seconds<S: Serializer>(time: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+    time.map(|time| time.as_secs()).serialize(serializer)
+}
+
+fn parse_time(time: &str) -> Result<Option<Duration>, Error> {
+    if time == ">24h" {
+        return Ok(None);
+    }
+    let invalid = || Error::Malformed(format!("time {:?}", time));
+    let mut secs = 0;
+    let mut fields = 0;
+    for field in time.split(':') {
+        secs = secs * 60 + field.parse::<u64>().map_err(|_| invalid())?;
+        fields += 1;
+    }
+    if fields != 3 {
+        return Err(invalid());
+    }
+    Ok(Some(Duration::from_secs(secs)))
+}
+
+/// The text of the first `<pre>` block with tags removed and entities decoded.
+fn pre_text(html: &str) -> Option<String> {
+    let start = html.find("<pre>")? + "<pre>".len();
+    let end = start + html[start..].find("</pre>")?;
+    Some(html_to_text(&html[start..end]))
+}
+
+/// Parse the personal leaderboard times page. Parts that aren't solved yet are
+/// left out.
+pub fn parse(html: &str) -> Result<Vec<Entry>, Error> {
+    let text = pre_text(html).ok_or(Error::NoStats)?;
+    let mut entries = Vec::new();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let day = match fields.first().and_then(|day| day.parse::<u8>().ok()) {
+            Some(day) => day,
+            None => continue,
+        };
+        if fields.len() != 7 {
+            return Err(Error::Malformed(line.to_string()));
+        }
+        for (part, columns) in (1..).zip(fields[1..].chunks(3)) {
+            if columns.iter().all(|column| *column == "-") {
+                continue;
+            }
+            let number = |column: &str| {
+                column
+                    .parse()
+                    .map_err(|_| Error::Malformed(line.to_string()))
+            };
+            entries.push(Entry {
+                day,
+                part,
+                time: parse_time(columns[0])?,
+                rank: number(columns[1])?,
+                score: number(columns[2])?,
+            });
+        }
+    }
+    entries.sort_by_key(|entry| (entry.day, entry.part));
+    Ok(entries)
+}
+
+pub fn render(entries: &[Entry]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:>3} {:>4} {:>8} {:>6} {:>5}",
+        "day", "part", "time", "rank", "score"
+    );
+    for entry in entries {
+        let time = entry
+            .time
+            .map(format_duration)
+            .unwrap_or_else(|| ">24h".to_string());
+        let _ = writeln!(
+            out,
+            "{:>3} {:>4} {:>8} {:>6} {:>5}",
+            entry.day, entry.part, time, entry.rank, entry.score
+        );
+    }
+    out
+}
+
+/// Fetch and parse the personal stats page. Like every other page it goes
+/// through the response cache, so this also works offline once cached.
+pub fn get_stats(config: &Config) -> Result<Vec<Entry>, Error> {
+    let body = fetch(config, &url(), Resource::Stats, validate_page)?;
+    parse(&String::from_utf8_lossy(&body))
+}
+
+/// The stats from the last time the page was fetched, without touching the
+/// network.
+pub fn load_cached(config: &Config) -> Result<Option<Vec<Entry>>, Error> {
+    match Cache::for_session(&config.session).load(&url())? {
+        Some((_, body)) => parse(&String::from_utf8_lossy(&body)).map(Some),
+        None => Ok(None),
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Fetch(#[from] crate::utils::Error),
+    #[error(transparent)]
+    Cache(#[from] crate::cache::Error),
+    #[error("no statistics found; is the session cookie still valid?")]
+    NoStats,
+    #[error("unexpected statistics line: {0}")]
+    Malformed(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../fixtures/stats.html");
+
+    #[test]
+    fn test_parse_fixture() {
+        let entries = parse(FIXTURE).unwrap();
+        assert_eq!(entries.len(), 13);
+        assert_eq!(
+            entries[0],
+            Entry {
+                day: 1,
+                part: 1,
+                time: Some(Duration::from_secs(118)),
+                rank: 71,
+                score: 30
+            }
+        );
+        assert_eq!(entries[6].time, None);
+        assert_eq!(entries[6].rank, 70021);
+        assert_eq!((entries[8].day, entries[8].part), (5, 1));
+        assert_eq!((entries[9].day, entries[9].part), (6, 1));
+    }
+
+    #[test]
+    fn test_parse_without_stats() {
+        assert!(matches!(
+            parse("<html><body>log in</body></html>"),
+            Err(Error::NoStats)
+        ));
+    }
+}
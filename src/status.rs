@@ -0,0 +1,297 @@
+use std::{fmt::Write, path::Path};
+
+use thiserror::Error;
+
+use crate::{config::Config, day::workspace_members, stats, verify::Answers};
+
+/// How far along a part's solution function is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    /// No crate, or no such function in its `lib.rs`
+    Missing,
+    /// The function still calls `unimplemented!()` or `todo!()`
+    Unimplemented,
+    Implemented,
+}
+
+impl Part {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Missing => "-",
+            Self::Unimplemented => "todo",
+            Self::Implemented => "done",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayStatus {
+    pub day: u8,
+    /// Listed in `[workspace] members`
+    pub member: bool,
+    /// The `dayNN` directory exists
+    pub crate_dir: bool,
+    pub input: bool,
+    pub parts: [Part; 2],
+    /// Stars according to the last fetched stats page or, if it was never
+    /// fetched, the answers recorded in `answers.toml`
+    pub stars: Option<u8>,
+}
+
+/// The body of `fn name(...)` in `source`, found by matching braces. Braces in
+/// comments and in string and char literals don't count.
+fn function_body<'a>(source: &'a str, name: &str) -> Option<&'a str> {
+    let start = source.find(&format!("fn {}(", name))?;
+    let open = start + source[start..].find('{')?;
+    let mut depth = 0;
+    let mut i = open;
+    while i < source.len() {
+        match source.as_bytes()[i] {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&source[open..=i]);
+                }
+            }
+            _ => i = token_end(source, i),
+        }
+        i += 1;
+    }
+    None
+}
+
+/// The index of the last byte of the comment or literal starting at `i`, or
+/// `i` itself if none does. Past the end if it is never closed.
+fn token_end(source: &str, i: usize) -> usize {
+    let bytes = source.as_bytes();
+    let at = |j: usize| bytes.get(j).copied();
+    let find = |from: usize, pattern: &str| {
+        source
+            .get(from..)
+            .and_then(|rest| rest.find(pattern))
+            .map(|offset| from + offset)
+    };
+    let is_ident = |j: usize| at(j).is_some_and(|b| b.is_ascii_alphanumeric() || b == b'_');
+
+    match (bytes[i], at(i + 1)) {
+        (b'/', Some(b'/')) => find(i, "\n").unwrap_or(source.len()) - 1,
+        (b'/', Some(b'*')) => {
+            // block comments nest
+            let (mut depth, mut j) = (1, i + 2);
+            while j < bytes.len() {
+                match (bytes[j], at(j + 1)) {
+                    (b'/', Some(b'*')) => (depth, j) = (depth + 1, j + 2),
+                    (b'*', Some(b'/')) if depth == 1 => return j + 1,
+                    (b'*', Some(b'/')) => (depth, j) = (depth - 1, j + 2),
+                    _ => j += 1,
+                }
+            }
+            source.len()
+        }
+        (b'"', _) => {
+            let mut j = i + 1;
+            while j < bytes.len() {
+                match bytes[j] {
+                    b'\\' => j += 2,
+                    b'"' => return j,
+                    _ => j += 1,
+                }
+            }
+            source.len()
+        }
+        // r"..." and r#"..."#, also as byte strings
+        (b'r', _)
+            if !is_ident(i.wrapping_sub(1))
+                || at(i.wrapping_sub(1)) == Some(b'b') && !is_ident(i.wrapping_sub(2)) =>
+        {
+            let hashes = bytes[i + 1..].iter().take_while(|&&b| b == b'#').count();
+            if at(i + 1 + hashes) != Some(b'"') {
+                return i;
+            }
+            let close = format!("\"{}", "#".repeat(hashes));
+            find(i + 2 + hashes, &close).map_or(source.len(), |j| j + hashes)
+        }
+        (b'\'', Some(b'\\')) => find(i + 3, "'").unwrap_or(source.len()),
+        (b'\'', _) => {
+            // a char literal, unless it is a lifetime or label
+            let len = source[i + 1..].chars().next().map_or(0, char::len_utf8);
+            match at(i + 1 + len) {
+                Some(b'\'') if len > 0 => i + 1 + len,
+                _ => i,
+            }
+        }
+        _ => i,
+    }
+}
+
+fn part_state(source: &str, name: &str) -> Part {
+    match function_body(source, name) {
+        None => Part::Missing,
+        Some(body) if body.contains("unimplemented!") || body.contains("todo!") => {
+            Part::Unimplemented
+        }
+        Some(_) => Part::Implemented,
+    }
+}
+
+/// Stars for `day` from the stats page if there is one, or else one for every
+/// part with a recorded answer. `None` if neither is available.
+fn stars(stats: Option<&[stats::Entry]>, answers: Option<&Answers>, day: u8) -> Option<u8> {
+    match (stats, answers) {
+        (Some(entries), _) => Some(entries.iter().filter(|entry| entry.day == day).count() as u8),
+        (None, Some(answers)) => Some(
+            (1..=2)
+                .filter(|&part| answers.get(day, part).is_some())
+                .count() as u8,
+        ),
+        (None, None) => None,
+    }
+}
+
+/// Collect the local state of days 1 to 25 in the workspace at `root`. Nothing
+/// here touches the network; stars come from the cached stats page, or from
+/// `answers.toml` if the page was never fetched.
+pub fn status(root: &Path, config: &Config) -> Result<Vec<DayStatus>, Error> {
+    let members = workspace_members(root)?;
+    let stats = stats::load_cached(config).ok().flatten();
+    let answers_path = config.answers_path();
+    let answers = match answers_path.exists() {
+        true => Answers::load(&answers_path).ok(),
+        false => None,
+    };
+
+    Ok((1..=25)
+        .map(|day| {
+            let name = format!("day{:02}", day);
+            let crate_dir = root.join(&name);
+            let parts = match std::fs::read_to_string(crate_dir.join("src").join("lib.rs")) {
+                Ok(source) => [part_state(&source, "part1"), part_state(&source, "part2")],
+                Err(_) => [Part::Missing; 2],
+            };
+            let stars = stars(stats.as_deref(), answers.as_ref(), day);
+            DayStatus {
+                day,
+                member: members.contains(&name),
+                crate_dir: crate_dir.is_dir(),
+                input: config.input_for(day).exists(),
+                parts,
+                stars,
+            }
+        })
+        .collect())
+}
+
+pub fn render(days: &[DayStatus]) -> String {
+    let yes_no = |b: bool| if b { "yes" } else { "-" };
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:>3}  {:<6}  {:<5}  {:<5}  {:<6}  {:<6}  stars",
+        "day", "member", "crate", "input", "part 1", "part 2"
+    );
+    for day in days {
+        let stars = match day.stars {
+            Some(stars) => "*".repeat(stars as usize),
+            None => "?".to_string(),
+        };
+        let _ = writeln!(
+            out,
+            "{:>3}  {:<6}  {:<5}  {:<5}  {:<6}  {:<6}  {}",
+            day.day,
+            yes_no(day.member),
+            yes_no(day.crate_dir),
+            yes_no(day.input),
+            day.parts[0].as_str(),
+            day.parts[1].as_str(),
+            stars
+        );
+    }
+    out
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Workspace(#[from] crate::day::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_part_state() {
+        let source = r#"
+pub fn part1(input: &Path) -> Result<(), Error> {
+    let depths: Vec<u64> = parse(input)?.collect();
+    if depths.is_empty() {
+        return Ok(());
+    }
+    println!("{}", depths.len());
+    Ok(())
+}
+
+pub fn part2(_input: &Path) -> Result<(), Error> {
+    unimplemented!()
+}
+"#;
+        assert_eq!(part_state(source, "part1"), Part::Implemented);
+        assert_eq!(part_state(source, "part2"), Part::Unimplemented);
+        assert_eq!(part_state(source, "part3"), Part::Missing);
+    }
+
+    #[test]
+    fn test_function_body_skips_literals_and_comments() {
+        let source = r##"
+pub fn part1(input: &str) -> usize {
+    'outer: loop {
+        break 'outer;
+    }
+    // a stray } in a comment
+    /* and { in /* nested */ block } comments */
+    let open = '{';
+    let escaped = '\'';
+    let text = "}\"}";
+    let raw = r#"}"}"#;
+    input.matches(['}', open]).count() + text.len() + raw.len()
+}
+
+pub fn part2(_input: &str) -> usize {
+    unimplemented!()
+}
+"##;
+        let body = function_body(source, "part1").unwrap();
+        assert!(body.ends_with("raw.len()\n}"));
+        assert_eq!(part_state(source, "part1"), Part::Implemented);
+        assert_eq!(part_state(source, "part2"), Part::Unimplemented);
+    }
+
+    #[test]
+    fn test_stars() {
+        let answers =
+            Answers::parse("[day01]\npart1 = 1675\npart2 = 1722\n[day02]\npart1 = 1\n").unwrap();
+        assert_eq!(stars(None, Some(&answers), 1), Some(2));
+        assert_eq!(stars(None, Some(&answers), 2), Some(1));
+        assert_eq!(stars(None, Some(&answers), 3), Some(0));
+        assert_eq!(stars(None, None, 1), None);
+
+        // the stats page wins over recorded answers
+        let entry = stats::Entry {
+            day: 2,
+            part: 1,
+            time: None,
+            rank: 1,
+            score: 0,
+        };
+        assert_eq!(
+            stars(
+                Some(&[entry.clone(), stats::Entry { part: 2, ..entry }]),
+                Some(&answers),
+                2
+            ),
+            Some(2)
+        );
+        assert_eq!(stars(Some(&[]), Some(&answers), 1), Some(0));
+    }
+}
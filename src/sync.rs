@@ -0,0 +1,293 @@
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::{
+    config::Config,
+    day::{render_templates, workspace_members, Context},
+    template::{Record, DEFAULT},
+    transaction::Transaction,
+    utils::{three_way_diff, unified_diff},
+};
+
+/// What `sync` did, or would do, with one generated file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Already identical to the new render, or the template hasn't changed
+    UpToDate,
+    /// Untouched since it was generated, so replaced by the new render
+    Updated,
+    /// Edited by hand; left alone, with the diff to look at instead
+    Edited(String),
+    /// No record says it was generated and it differs from the render; left
+    /// alone, with the diff against the render to look at instead
+    Unrecorded(String),
+    /// Had no record, so replaced by the render as asked and recorded from now
+    /// on
+    Adopted,
+    /// Deleted since it was generated; left deleted
+    Missing,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSync {
+    /// Relative to the workspace root
+    pub path: PathBuf,
+    pub outcome: Outcome,
+}
+
+/// Decide what to do with a generated file given the render it was last
+/// brought in line with, what it holds now and the new render.
+fn outcome(path: &str, base: &str, current: Option<&str>, new: &str) -> Outcome {
+    match current {
+        None => Outcome::Missing,
+        Some(current) if current == new || base == new => Outcome::UpToDate,
+        Some(current) if current == base => Outcome::Updated,
+        Some(current) => Outcome::Edited(three_way_diff(path, base, current, new)),
+    }
+}
+
+/// Re-render the generated files of every `dayNN` workspace member from its
+/// template set and update those nobody has edited since they were generated.
+/// Days without a record are taken to be generated from the default set where
+/// their files still match its render. Other files no record covers are only
+/// diffed against the render, unless they are listed in `adopt`, relative to
+/// the workspace root: those are replaced by the render and recorded.
+/// Nothing is written with `dry_run`.
+pub fn sync(
+    config: &Config,
+    root: &Path,
+    dry_run: bool,
+    adopt: &[PathBuf],
+) -> Result<Vec<FileSync>, Error> {
+    let mut synced = Vec::new();
+    let mut writes = Vec::new();
+
+    for name in workspace_members(root)? {
+        let day = match name
+            .strip_prefix("day")
+            .and_then(|day| day.parse::<u8>().ok())
+        {
+            Some(day) => day,
+            None => continue,
+        };
+        let crate_dir = root.join(&name);
+        if !crate_dir.is_dir() {
+            continue;
+        }
+
+        let record = Record::load(&crate_dir)?;
+        let mut context = Context::new(config, day, &name)?;
+        let set = match record {
+            Some(ref record) => {
+                context.date = record.date.clone();
+                record.set.clone()
+            }
+            None => DEFAULT.to_string(),
+        };
+        let rendered = render_templates(root, &set, &context)?;
+
+        let mut new_record = Record::new(&set, &context.date, &[]);
+        for (path, text) in rendered {
+            let target = crate_dir.join(&path);
+            let current = if target.exists() {
+                Some(std::fs::read_to_string(&target)?)
+            } else {
+                None
+            };
+            let base = match record {
+                Some(ref record) => record.files.get(&path).cloned(),
+                // a day from before renders were recorded counts as generated
+                // wherever it still matches the render made for it
+                None => current.clone().filter(|current| *current == text),
+            };
+            let label = Path::new(&name).join(&path);
+            let base = match base {
+                Some(base) => base,
+                None => {
+                    let outcome = if adopt.contains(&label) {
+                        writes.push((target, text.clone()));
+                        new_record.files.insert(path, text);
+                        Outcome::Adopted
+                    } else {
+                        let current = current.unwrap_or_default();
+                        Outcome::Unrecorded(unified_diff(&label.to_string_lossy(), &current, &text))
+                    };
+                    synced.push(FileSync {
+                        path: label,
+                        outcome,
+                    });
+                    continue;
+                }
+            };
+            let outcome = outcome(&label.to_string_lossy(), &base, current.as_deref(), &text);
+
+            // the record keeps the render each file was last reconciled with;
+            // a hand-edited file keeps its old base so the template change
+            // keeps being reported until it is merged
+            match outcome {
+                Outcome::Updated => {
+                    writes.push((target, text.clone()));
+                    new_record.files.insert(path, text);
+                }
+                Outcome::UpToDate => {
+                    new_record.files.insert(path, text);
+                }
+                Outcome::Edited(_) | Outcome::Missing => {
+                    new_record.files.insert(path, base);
+                }
+                Outcome::Unrecorded(_) | Outcome::Adopted => {}
+            }
+            synced.push(FileSync {
+                path: label,
+                outcome,
+            });
+        }
+
+        let adopted = record.is_some() || !new_record.files.is_empty();
+        if adopted && record.as_ref() != Some(&new_record) {
+            writes.push((crate_dir.join(Record::FILE), new_record.to_json()));
+        }
+    }
+
+    // only files sync would otherwise leave alone can be adopted
+    if let Some(path) = adopt.iter().find(|path| {
+        !synced
+            .iter()
+            .any(|file| file.path == **path && file.outcome == Outcome::Adopted)
+    }) {
+        return Err(Error::NotUnrecorded(path.clone()));
+    }
+
+    if !dry_run {
+        let mut transaction = Transaction::default();
+        for (path, text) in writes {
+            if path.exists() {
+                transaction.replace_file(&path, &text)?;
+            } else {
+                transaction.create_file(&path, &text)?;
+            }
+        }
+        transaction.commit();
+    }
+    Ok(synced)
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Workspace(#[from] crate::day::Error),
+    #[error(transparent)]
+    Template(#[from] crate::template::Error),
+    #[error("{} is not a generated file without a template record", .0.display())]
+    NotUnrecorded(PathBuf),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_outcome() {
+        let path = "day01/src/main.rs";
+        assert_eq!(outcome(path, "a\n", None, "b\n"), Outcome::Missing);
+        assert_eq!(outcome(path, "a\n", Some("b\n"), "b\n"), Outcome::UpToDate);
+        assert_eq!(outcome(path, "a\n", Some("c\n"), "a\n"), Outcome::UpToDate);
+        assert_eq!(outcome(path, "a\n", Some("a\n"), "b\n"), Outcome::Updated);
+        match outcome(path, "a\n", Some("c\n"), "b\n") {
+            Outcome::Edited(diff) => {
+                assert!(diff.contains("+c"));
+                assert!(diff.contains("+b"));
+                assert!(diff.contains("(theirs)"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_sync_unrecorded_day() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let day_dir = root.join("day01");
+        std::fs::create_dir_all(day_dir.join("src")).unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"aoc2021\"\n\n[workspace]\nmembers = [\"day01\"]\n",
+        )
+        .unwrap();
+        for file in ["Cargo.toml", "src/lib.rs", "src/main.rs"] {
+            let original = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("day01")
+                .join(file);
+            std::fs::copy(original, day_dir.join(file)).unwrap();
+        }
+        let config = Config {
+            input_files: Some(root.join("input")),
+            ..Config::default()
+        };
+        let outcomes = |synced: Vec<FileSync>| -> Vec<(String, Outcome)> {
+            synced
+                .into_iter()
+                .map(|file| (file.path.to_string_lossy().into_owned(), file.outcome))
+                .collect()
+        };
+
+        // main.rs is a copy from before a template change
+        let main = day_dir.join("src/main.rs");
+        let render = std::fs::read_to_string(&main).unwrap();
+        let stale = render.replace("\"fetch input\"", "\"input\"");
+        std::fs::write(&main, &stale).unwrap();
+
+        let synced = sync(&config, root, false, &[]).unwrap();
+        let synced = outcomes(synced);
+        assert_eq!(synced[0].1, Outcome::UpToDate);
+        assert!(matches!(synced[1].1, Outcome::Unrecorded(_)));
+        match &synced[2].1 {
+            Outcome::Unrecorded(diff) => {
+                assert!(diff.contains("+++ b/day01/src/main.rs"));
+                assert!(diff.contains("-    let input_path = timing::phase(\"input\""));
+                assert!(diff.contains("+    let input_path = timing::phase(\"fetch input\""));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(std::fs::read_to_string(&main).unwrap(), stale);
+        let record = Record::load(&day_dir).unwrap().unwrap();
+        let recorded: Vec<_> = record.files.keys().cloned().collect();
+        assert_eq!(recorded, [PathBuf::from("Cargo.toml")]);
+
+        assert!(matches!(
+            sync(&config, root, false, &[PathBuf::from("day01/Cargo.toml")]),
+            Err(Error::NotUnrecorded(_))
+        ));
+        let adopt = [PathBuf::from("day01/src/main.rs")];
+        let synced = sync(&config, root, false, &adopt).unwrap();
+        assert_eq!(outcomes(synced)[2].1, Outcome::Adopted);
+        assert_eq!(std::fs::read_to_string(&main).unwrap(), render);
+        let record = Record::load(&day_dir).unwrap().unwrap();
+        let recorded: Vec<_> = record.files.keys().cloned().collect();
+        assert_eq!(
+            recorded,
+            [PathBuf::from("Cargo.toml"), PathBuf::from("src/main.rs")]
+        );
+
+        // once recorded, main.rs follows the template while nobody edits it
+        let older = render.replace("const DAY", "// an older render\nconst DAY");
+        let mut record = record;
+        record
+            .files
+            .insert(PathBuf::from("src/main.rs"), older.clone());
+        std::fs::write(day_dir.join(Record::FILE), record.to_json()).unwrap();
+        std::fs::write(&main, &older).unwrap();
+
+        let synced = sync(&config, root, false, &[]).unwrap();
+        assert_eq!(outcomes(synced)[2].1, Outcome::Updated);
+        assert_eq!(std::fs::read_to_string(&main).unwrap(), render);
+        let lib = Path::new(env!("CARGO_MANIFEST_DIR")).join("day01/src/lib.rs");
+        assert_eq!(
+            std::fs::read_to_string(day_dir.join("src/lib.rs")).unwrap(),
+            std::fs::read_to_string(lib).unwrap()
+        );
+    }
+}
@@ -0,0 +1,478 @@
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tinytemplate::TinyTemplate;
+use toml_edit::Document;
+
+/// The set `init` uses unless told otherwise.
+pub const DEFAULT: &str = "plain";
+const MANIFEST: &str = "manifest.toml";
+
+/// Where user-provided template sets live, one directory per set.
+pub fn user_dir() -> PathBuf {
+    crate::config::path().with_file_name("templates")
+}
+
+/// The built-in sets compiled into the binary, so `init` works even where the
+/// workspace has no `day-template` directories of its own.
+const EMBEDDED: &[(&str, &[(&str, &str)])] = &[
+    (
+        DEFAULT,
+        &[
+            (MANIFEST, include_str!("../day-template/manifest.toml")),
+            (
+                "Cargo.toml.tmpl",
+                include_str!("../day-template/Cargo.toml.tmpl"),
+            ),
+            (
+                "src/lib.rs.tmpl",
+                include_str!("../day-template/src/lib.rs.tmpl"),
+            ),
+            (
+                "src/main.rs.tmpl",
+                include_str!("../day-template/src/main.rs.tmpl"),
+            ),
+        ],
+    ),
+    (
+        "grid",
+        &[
+            (MANIFEST, include_str!("../day-template-grid/manifest.toml")),
+            (
+                "src/lib.rs.tmpl",
+                include_str!("../day-template-grid/src/lib.rs.tmpl"),
+            ),
+        ],
+    ),
+    (
+        "single-line-csv",
+        &[
+            (
+                MANIFEST,
+                include_str!("../day-template-single-line-csv/manifest.toml"),
+            ),
+            (
+                "src/lib.rs.tmpl",
+                include_str!("../day-template-single-line-csv/src/lib.rs.tmpl"),
+            ),
+        ],
+    ),
+];
+
+/// Where the files of a template set are read from.
+#[derive(Debug, Clone)]
+pub enum Location {
+    Dir(PathBuf),
+    Embedded(&'static [(&'static str, &'static str)]),
+}
+
+impl Location {
+    /// The contents of `file` relative to the set, if the set has it.
+    fn read(&self, file: &Path) -> Result<Option<String>, Error> {
+        match self {
+            Self::Dir(dir) => {
+                let path = dir.join(file);
+                if !path.is_file() {
+                    return Ok(None);
+                }
+                Ok(Some(std::fs::read_to_string(path)?))
+            }
+            Self::Embedded(files) => Ok(files
+                .iter()
+                .find(|(name, _)| Path::new(name) == file)
+                .map(|(_, text)| text.to_string())),
+        }
+    }
+}
+
+/// Places a set called `name` may live in, in order of preference: the user's
+/// own sets in `user_dir` shadow the ones in the workspace, which shadow the
+/// embedded ones.
+fn candidates(user_dir: &Path, workspace: &Path, name: &str) -> Vec<Location> {
+    let builtin = match name {
+        DEFAULT => workspace.join("day-template"),
+        name => workspace.join(format!("day-template-{}", name)),
+    };
+    let mut candidates = vec![Location::Dir(user_dir.join(name)), Location::Dir(builtin)];
+    candidates.extend(
+        EMBEDDED
+            .iter()
+            .filter(|(embedded, _)| *embedded == name)
+            .map(|(_, files)| Location::Embedded(files)),
+    );
+    candidates
+}
+
+/// Templates format values as is, and as a Rust raw string literal with
+/// `raw_string`. The literal gets one more `#` than any run following a quote
+/// in the text, so examples like `"#` can't end it early.
+fn format_raw_string(
+    value: &serde_json::Value,
+    output: &mut String,
+) -> tinytemplate::error::Result<()> {
+    let mut text = String::new();
+    tinytemplate::format_unescaped(value, &mut text)?;
+    let hashes = text
+        .split('"')
+        .skip(1)
+        .map(|rest| rest.len() - rest.trim_start_matches('#').len() + 1)
+        .max()
+        .unwrap_or(1);
+    let hashes = "#".repeat(hashes);
+    output.push_str(&format!("r{}\"{}\"{}", hashes, text, hashes));
+    Ok(())
+}
+
+/// Whether `path` stays inside the directory it is joined onto: relative and
+/// without `..`, so a set can't write outside the day's crate.
+fn is_inside(path: &Path) -> bool {
+    path.components().count() > 0
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// A set of templates with a `manifest.toml` listing the files it
+/// generates. A file `path` is rendered from `path.tmpl`, or copied verbatim
+/// from `path` if there is no template; files the set doesn't have are taken
+/// from its `base` set.
+#[derive(Debug, Clone)]
+pub struct TemplateSet {
+    pub name: String,
+    pub location: Location,
+    pub description: Option<String>,
+    pub files: Vec<PathBuf>,
+    base: Option<Box<TemplateSet>>,
+}
+
+impl TemplateSet {
+    pub fn load(workspace: &Path, name: &str) -> Result<Self, Error> {
+        Self::load_from(&user_dir(), workspace, name)
+    }
+
+    /// Like `load`, with the user's sets in `user_dir`.
+    pub(crate) fn load_from(user_dir: &Path, workspace: &Path, name: &str) -> Result<Self, Error> {
+        Self::load_with_parents(user_dir, workspace, name, &mut Vec::new())
+    }
+
+    fn load_with_parents(
+        user_dir: &Path,
+        workspace: &Path,
+        name: &str,
+        parents: &mut Vec<String>,
+    ) -> Result<Self, Error> {
+        // a name is joined onto the user's and the workspace's template dirs
+        if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+            return Err(Error::InvalidName(name.to_string()));
+        }
+        if parents.iter().any(|parent| parent == name) {
+            return Err(Error::RecursiveBase(name.to_string()));
+        }
+        let mut found = None;
+        for location in candidates(user_dir, workspace, name) {
+            if let Some(manifest) = location.read(Path::new(MANIFEST))? {
+                found = Some((location, manifest));
+                break;
+            }
+        }
+        let (location, manifest) = found.ok_or_else(|| Error::Unknown(name.to_string()))?;
+
+        let manifest = Document::from_str(&manifest)
+            .map_err(|err| Error::ParseManifest(err, name.to_string()))?;
+        let malformed = || Error::MalformedManifest(name.to_string());
+        let string = |key| {
+            manifest
+                .as_table()
+                .get(key)
+                .and_then(|item| item.as_str())
+                .map(str::to_string)
+        };
+        let files = manifest
+            .as_table()
+            .get("files")
+            .and_then(|item| item.as_array())
+            .ok_or_else(malformed)?
+            .iter()
+            .map(|file| {
+                file.as_str()
+                    .map(PathBuf::from)
+                    .filter(|path| is_inside(path))
+                    .ok_or_else(malformed)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let base = match string("base") {
+            Some(base) => {
+                parents.push(name.to_string());
+                Some(Box::new(Self::load_with_parents(
+                    user_dir, workspace, &base, parents,
+                )?))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            location,
+            description: string("description"),
+            files,
+            base,
+        })
+    }
+
+    /// The source for `file` and whether it is a template to be rendered.
+    fn source(&self, file: &Path) -> Result<(String, bool), Error> {
+        let mut template = file.as_os_str().to_owned();
+        template.push(".tmpl");
+        if let Some(text) = self.location.read(Path::new(&template))? {
+            return Ok((text, true));
+        }
+        if let Some(text) = self.location.read(file)? {
+            return Ok((text, false));
+        }
+        match self.base {
+            Some(ref base) => base.source(file),
+            None => Err(Error::MissingFile(file.to_owned(), self.name.clone())),
+        }
+    }
+
+    /// Render every file of the set in memory, returning each file's path
+    /// relative to the new crate together with its contents.
+    pub fn render<C: Serialize>(&self, context: &C) -> Result<Vec<(PathBuf, String)>, Error> {
+        let mut rendered = Vec::new();
+        for file in self.files.iter() {
+            let (text, is_template) = self.source(file)?;
+            if !is_template {
+                rendered.push((file.clone(), text));
+                continue;
+            }
+
+            let name = file.to_string_lossy();
+            let mut tt = TinyTemplate::new();
+            // the output is Rust and TOML, not HTML
+            tt.set_default_formatter(&tinytemplate::format_unescaped);
+            tt.add_formatter("raw_string", format_raw_string);
+            tt.add_template(&name, &text)
+                .map_err(|err| Error::Render(err, name.to_string()))?;
+            let rendered_text = tt
+                .render(&name, context)
+                .map_err(|err| Error::Render(err, name.to_string()))?;
+            rendered.push((file.clone(), rendered_text));
+        }
+        Ok(rendered)
+    }
+}
+
+/// What a day crate was last rendered from, kept next to its `Cargo.toml` so
+/// `template sync` can tell hand edits from template changes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    pub set: String,
+    /// The `date` the crate was generated on, reused when re-rendering
+    pub date: String,
+    /// Every generated file as it was rendered
+    pub files: BTreeMap<PathBuf, String>,
+}
+
+impl Record {
+    pub const FILE: &'static str = ".template.json";
+
+    pub fn new(set: &str, date: &str, rendered: &[(PathBuf, String)]) -> Self {
+        Self {
+            set: set.to_string(),
+            date: date.to_string(),
+            files: rendered.iter().cloned().collect(),
+        }
+    }
+
+    /// The record of the crate in `crate_dir`, if it has one.
+    pub fn load(crate_dir: &Path) -> Result<Option<Self>, Error> {
+        let path = crate_dir.join(Self::FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        serde_json::from_str(&std::fs::read_to_string(&path)?)
+            .map(Some)
+            .map_err(|err| Error::ParseRecord(err, path))
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut json = serde_json::to_string_pretty(self).unwrap_or_default();
+        json.push('\n');
+        json
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("no template set named {0}")]
+    Unknown(String),
+    #[error("could not parse manifest of template set {1}")]
+    ParseManifest(#[source] toml_edit::TomlError, String),
+    #[error("manifest of template set {0} must list its files as an array of relative paths")]
+    MalformedManifest(String),
+    #[error("invalid template set name {0:?}")]
+    InvalidName(String),
+    #[error("template set {0} is its own base")]
+    RecursiveBase(String),
+    #[error("template set {1} has no file {}", .0.display())]
+    MissingFile(PathBuf, String),
+    #[error("template error for {1}")]
+    Render(#[source] tinytemplate::error::Error, String),
+    #[error("could not parse template record {}", .1.display())]
+    ParseRecord(#[source] serde_json::Error, PathBuf),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_builtin_sets() {
+        let workspace = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let user_dir = tempfile::tempdir().unwrap();
+        #[derive(Serialize)]
+        struct Context {
+            year: u16,
+            day: u8,
+            day_padded: String,
+            package_name: String,
+            url: String,
+            title: Option<String>,
+            date: String,
+            author: Option<String>,
+            examples: Vec<String>,
+        }
+        let context = Context {
+            year: 2021,
+            day: 9,
+            day_padded: "09".to_string(),
+            package_name: "day09".to_string(),
+            url: "https://adventofcode.com/2021/day/9".to_string(),
+            title: Some("Smoke Basin".to_string()),
+            date: "2021-12-09".to_string(),
+            author: None,
+            examples: vec!["2199943210\n3987894921".to_string()],
+        };
+
+        for name in [DEFAULT, "grid", "single-line-csv"] {
+            let set = TemplateSet::load_from(user_dir.path(), workspace, name).unwrap();
+            let rendered = set.render(&context).unwrap();
+            let main = rendered
+                .iter()
+                .find(|(path, _)| path == Path::new("src/main.rs"))
+                .unwrap();
+            assert!(main.1.contains("const DAY: u8 = 9;"));
+            let lib = rendered
+                .iter()
+                .find(|(path, _)| path == Path::new("src/lib.rs"))
+                .unwrap();
+            assert!(lib.1.contains("Smoke Basin"));
+            assert!(lib.1.contains("r#\"2199943210\n3987894921\"#"));
+            assert!(lib.1.contains("example0(EXAMPLES[0])"));
+        }
+        assert!(matches!(
+            TemplateSet::load_from(user_dir.path(), workspace, "no-such-set"),
+            Err(Error::Unknown(_))
+        ));
+    }
+
+    #[test]
+    fn test_raw_string() {
+        let raw_string = |text: &str| {
+            let mut output = String::new();
+            format_raw_string(&serde_json::Value::from(text), &mut output).unwrap();
+            output
+        };
+        assert_eq!(raw_string("1,2,3"), "r#\"1,2,3\"#");
+        assert_eq!(raw_string("#.\n\"#"), "r##\"#.\n\"#\"##");
+        assert_eq!(
+            raw_string("\"###\" and \"#"),
+            "r####\"\"###\" and \"#\"####"
+        );
+    }
+
+    #[test]
+    fn test_rejects_paths_outside_the_crate() {
+        let temp = tempfile::tempdir().unwrap();
+        let workspace = temp.path();
+        for (name, file) in [
+            ("absolute", "/etc/x"),
+            ("parent", "../../src/main.rs"),
+            ("nested-parent", "src/../../x"),
+        ] {
+            let dir = workspace.join(format!("day-template-{}", name));
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(MANIFEST), format!("files = [{:?}]\n", file)).unwrap();
+            assert!(
+                matches!(
+                    TemplateSet::load_from(workspace, workspace, name),
+                    Err(Error::MalformedManifest(_))
+                ),
+                "{}",
+                file
+            );
+        }
+        for name in ["../day-template", "a/b", "a\\b", "..", ""] {
+            assert!(
+                matches!(
+                    TemplateSet::load_from(workspace, workspace, name),
+                    Err(Error::InvalidName(_))
+                ),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_embedded_sets() {
+        let nowhere = Path::new("/nonexistent/workspace");
+        for name in [DEFAULT, "grid", "single-line-csv"] {
+            let set = TemplateSet::load_from(nowhere, nowhere, name).unwrap();
+            assert!(matches!(set.location, Location::Embedded(_)));
+            assert!(set.source(Path::new("src/main.rs")).unwrap().1);
+        }
+
+        // the embedded copies must keep up with the workspace's sets
+        let workspace = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let user_dir = tempfile::tempdir().unwrap();
+        let mut dirs: Vec<String> = std::fs::read_dir(workspace)
+            .unwrap()
+            .filter_map(|entry| entry.unwrap().file_name().into_string().ok())
+            .filter(|name| name.starts_with("day-template"))
+            .collect();
+        dirs.sort();
+        let mut embedded: Vec<String> = EMBEDDED
+            .iter()
+            .map(|(name, _)| match *name {
+                DEFAULT => "day-template".to_string(),
+                name => format!("day-template-{}", name),
+            })
+            .collect();
+        embedded.sort();
+        assert_eq!(dirs, embedded);
+        for (name, _) in EMBEDDED {
+            let from_dir = TemplateSet::load_from(user_dir.path(), workspace, name).unwrap();
+            let from_binary = TemplateSet::load_from(nowhere, nowhere, name).unwrap();
+            assert!(matches!(from_dir.location, Location::Dir(_)));
+            assert_eq!(from_binary.files, from_dir.files, "{}", name);
+            for file in from_dir.files.iter() {
+                assert_eq!(
+                    from_binary.source(file).unwrap(),
+                    from_dir.source(file).unwrap(),
+                    "{} in {}",
+                    file.display(),
+                    name
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! Wall-clock timing of the phases of a solution, as shown by `--time`.
+//!
+//! Solutions mark their phases with [`phase`], which costs nothing unless the
+//! binary was asked to time them. Phases nest: one marked while another runs
+//! is shown indented below it. With the `count-allocations` feature, what
+//! every phase allocated is recorded too.
+
+use std::{
+    cell::RefCell,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::allocations::{self, format_bytes, Usage};
+
+/// A phase that ran to completion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Phase {
+    pub name: &'static str,
+    /// How many phases it ran inside of
+    pub depth: usize,
+    pub duration: Duration,
+    /// Only counted with the `count-allocations` feature
+    pub memory: Option<Usage>,
+}
+
+#[derive(Debug, Default)]
+struct Recording {
+    /// In the order the phases started
+    phases: Vec<Phase>,
+    depth: usize,
+}
+
+thread_local! {
+    static RECORDING: RefCell<Option<Recording>> = const { RefCell::new(None) };
+}
+
+/// Start recording phases on this thread, dropping any recorded so far.
+pub fn enable() {
+    RECORDING.with(|recording| *recording.borrow_mut() = Some(Recording::default()));
+}
+
+/// Stop recording and return what was recorded.
+pub fn report() -> Report {
+    let recording = RECORDING.with(|recording| recording.borrow_mut().take());
+    Report(recording.unwrap_or_default().phases)
+}
+
+/// Run `f` as the phase `name`, timing it if recording is enabled.
+pub fn phase<T>(name: &'static str, f: impl FnOnce() -> T) -> T {
+    let slot = RECORDING.with(|recording| {
+        let mut recording = recording.borrow_mut();
+        let recording = recording.as_mut()?;
+        recording.phases.push(Phase {
+            name,
+            depth: recording.depth,
+            duration: Duration::ZERO,
+            memory: None,
+        });
+        recording.depth += 1;
+        Some(recording.phases.len() - 1)
+    });
+    let slot = match slot {
+        Some(slot) => slot,
+        None => return f(),
+    };
+
+    let start = Instant::now();
+    let (result, memory) = if allocations::enabled() {
+        let (result, usage) = allocations::measure(f);
+        (result, Some(usage))
+    } else {
+        (f(), None)
+    };
+    let duration = start.elapsed();
+
+    RECORDING.with(|recording| {
+        if let Some(recording) = recording.borrow_mut().as_mut() {
+            let phase = &mut recording.phases[slot];
+            phase.duration = duration;
+            phase.memory = memory;
+            recording.depth -= 1;
+        }
+    });
+    result
+}
+
+/// Durations to the nanosecond, in the largest unit under which they stay
+/// above one. Units are padded to two characters so decimal points line up.
+fn format_duration(duration: Duration) -> String {
+    let nanos = duration.as_nanos();
+    match nanos {
+        0..=999 => format!("{}ns", nanos),
+        1_000..=999_999 => format!("{:.3}µs", nanos as f64 / 1e3),
+        1_000_000..=999_999_999 => format!("{:.3}ms", nanos as f64 / 1e6),
+        _ => format!("{:.3}s ", nanos as f64 / 1e9),
+    }
+}
+
+/// The recorded phases, shown as an indented table. Phases with nested ones
+/// get an `other` line for the time not spent in any of them. Allocations are
+/// shown as their count, the bytes allocated and the peak heap size.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report(pub Vec<Phase>);
+
+/// Depth, label, duration and allocations
+type Row = (usize, &'static str, Duration, Option<Usage>);
+
+impl Report {
+    /// The lines of the table: depth, label, duration and allocations.
+    fn rows(&self) -> Vec<Row> {
+        /// A phase whose nested phases are still being listed
+        struct Open<'a> {
+            phase: &'a Phase,
+            nested: Duration,
+            has_nested: bool,
+        }
+
+        fn close(open: Open, rows: &mut Vec<Row>) {
+            if open.has_nested {
+                let other = open.phase.duration.saturating_sub(open.nested);
+                rows.push((open.phase.depth + 1, "other", other, None));
+            }
+        }
+
+        let mut rows = Vec::new();
+        let mut stack: Vec<Open> = Vec::new();
+        for phase in &self.0 {
+            while stack
+                .last()
+                .is_some_and(|open| open.phase.depth >= phase.depth)
+            {
+                close(stack.pop().unwrap(), &mut rows);
+            }
+            if let Some(parent) = stack.last_mut() {
+                parent.nested += phase.duration;
+                parent.has_nested = true;
+            }
+            rows.push((phase.depth, phase.name, phase.duration, phase.memory));
+            stack.push(Open {
+                phase,
+                nested: Duration::ZERO,
+                has_nested: false,
+            });
+        }
+        while let Some(open) = stack.pop() {
+            close(open, &mut rows);
+        }
+        rows
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = self.rows();
+        let width = rows
+            .iter()
+            .map(|(depth, name, _, _)| 2 * depth + name.len())
+            .max()
+            .unwrap_or_default();
+        for (depth, name, duration, memory) in rows {
+            let label = format!("{:indent$}{}", "", name, indent = 2 * depth);
+            let mut line = format!(
+                "{:width$}  {:>11}",
+                label,
+                format_duration(duration),
+                width = width
+            );
+            if let Some(memory) = memory {
+                line.push_str(&format!(
+                    "  {:>9} allocs  {:>9}  peak {:>9}",
+                    memory.allocations,
+                    format_bytes(memory.bytes),
+                    format_bytes(memory.peak)
+                ));
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_phases() {
+        assert_eq!(phase("ignored", || 1), 1);
+        assert_eq!(report(), Report::default());
+
+        enable();
+        let answer = phase("part 1", || {
+            let input = phase("parse", || "1,2,3".split(',').count());
+            phase("solve", || input * 2)
+        });
+        phase("part 2", || ());
+        assert_eq!(answer, 6);
+
+        let report = report();
+        let phases: Vec<_> = report
+            .0
+            .iter()
+            .map(|phase| (phase.depth, phase.name))
+            .collect();
+        assert_eq!(
+            phases,
+            [(0, "part 1"), (1, "parse"), (1, "solve"), (0, "part 2")]
+        );
+        assert!(report.0[0].duration >= report.0[1].duration + report.0[2].duration);
+    }
+
+    #[test]
+    fn test_report() {
+        let phase = |depth, name, micros| Phase {
+            name,
+            depth,
+            duration: Duration::from_micros(micros),
+            memory: None,
+        };
+        let report = Report(vec![
+            phase(0, "input", 12),
+            phase(0, "part 1", 2_500),
+            phase(1, "parse", 2_000),
+            phase(1, "solve", 400),
+            phase(0, "part 2", 1_500_000),
+        ]);
+        assert_eq!(
+            report.to_string(),
+            "\
+input       12.000µs
+part 1       2.500ms
+  parse      2.000ms
+  solve    400.000µs
+  other    100.000µs
+part 2       1.500s
+"
+        );
+        assert_eq!(format_duration(Duration::from_nanos(15)), "15ns");
+
+        let report = Report(vec![Phase {
+            memory: Some(Usage {
+                allocations: 12,
+                bytes: 3 * 1024,
+                peak: 2048,
+            }),
+            ..phase(0, "part 1", 2_500)
+        }]);
+        assert_eq!(
+            report.to_string(),
+            "part 1      2.500ms         12 allocs     3.0KiB  peak    2.0KiB\n"
+        );
+    }
+}
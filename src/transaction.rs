@@ -0,0 +1,125 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug)]
+enum Change {
+    CreatedDir(PathBuf),
+    CreatedFile(PathBuf),
+    Replaced(PathBuf, Vec<u8>),
+    Moved { from: PathBuf, to: PathBuf },
+}
+
+/// Filesystem changes that are undone, newest first, when dropped unless
+/// committed.
+#[derive(Debug, Default)]
+pub(crate) struct Transaction {
+    changes: Vec<Change>,
+    committed: bool,
+}
+
+impl Transaction {
+    pub(crate) fn create_dir_all(&mut self, path: &Path) -> std::io::Result<()> {
+        let missing: Vec<&Path> = path.ancestors().take_while(|dir| !dir.exists()).collect();
+        for dir in missing.into_iter().rev() {
+            std::fs::create_dir(dir)?;
+            self.changes.push(Change::CreatedDir(dir.to_owned()));
+        }
+        Ok(())
+    }
+
+    /// Create a new file, failing if it already exists.
+    pub(crate) fn create_file(&mut self, path: &Path, contents: &str) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            self.create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        self.changes.push(Change::CreatedFile(path.to_owned()));
+        file.write_all(contents.as_bytes())
+    }
+
+    /// Overwrite an existing file, remembering what it held before.
+    pub(crate) fn replace_file(&mut self, path: &Path, contents: &str) -> std::io::Result<()> {
+        let original = std::fs::read(path)?;
+        self.changes
+            .push(Change::Replaced(path.to_owned(), original));
+        std::fs::write(path, contents)
+    }
+
+    /// Move a file or directory, creating the destination's parents.
+    pub(crate) fn move_path(&mut self, from: &Path, to: &Path) -> std::io::Result<()> {
+        if let Some(parent) = to.parent() {
+            self.create_dir_all(parent)?;
+        }
+        std::fs::rename(from, to)?;
+        self.changes.push(Change::Moved {
+            from: from.to_owned(),
+            to: to.to_owned(),
+        });
+        Ok(())
+    }
+
+    pub(crate) fn commit(mut self) {
+        self.committed = true;
+    }
+
+    fn rollback(&mut self) {
+        for change in self.changes.drain(..).rev() {
+            let _ = match change {
+                Change::CreatedDir(dir) => std::fs::remove_dir(dir),
+                Change::CreatedFile(path) => std::fs::remove_file(path),
+                Change::Replaced(path, original) => std::fs::write(path, original),
+                Change::Moved { from, to } => std::fs::rename(to, from),
+            };
+        }
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        if !self.committed {
+            self.rollback();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rollback() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let manifest = dir.join("Cargo.toml");
+        std::fs::write(&manifest, "original").unwrap();
+
+        let mut transaction = Transaction::default();
+        transaction
+            .create_file(&dir.join("day09/src/lib.rs"), "lib")
+            .unwrap();
+        transaction
+            .move_path(&dir.join("day09/src/lib.rs"), &dir.join("backup/lib.rs"))
+            .unwrap();
+        transaction.replace_file(&manifest, "changed").unwrap();
+        assert!(transaction
+            .create_file(&dir.join("day09/src"), "again")
+            .is_err());
+        drop(transaction);
+
+        assert_eq!(std::fs::read_to_string(&manifest).unwrap(), "original");
+        assert!(!dir.join("day09").exists());
+        assert!(!dir.join("backup").exists());
+
+        let mut transaction = Transaction::default();
+        transaction
+            .create_file(&dir.join("day09/src/lib.rs"), "lib")
+            .unwrap();
+        transaction.commit();
+        assert!(dir.join("day09/src/lib.rs").exists());
+    }
+}
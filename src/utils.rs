@@ -1,6 +1,19 @@
-use crate::config::Config;
+use crate::{
+    cache::{Cache, Meta, Resource},
+    config::Config,
+};
+use fs2::FileExt;
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 use thiserror::Error;
 
+/// 2021-12-01T00:00:00 US Eastern (EST, UTC-5) as a unix timestamp.
+const FIRST_UNLOCK: u64 = 1_638_334_800;
+const DAYS: std::ops::RangeInclusive<u8> = 1..=25;
+
 pub fn url_for_day(day: u8) -> String {
     format!("https://adventofcode.com/{}/day/{}", 2021, day)
 }
@@ -9,51 +22,613 @@ pub fn input_url_for_day(day: u8) -> String {
     format!("{}/input", url_for_day(day))
 }
 
-pub fn get_input(config: &Config, day: u8) -> Result<(), Error> {
-    let input_path = config.input_for(day);
-    if input_path.exists() {
-        return Ok(());
+/// The instant the puzzle for `day` becomes available.
+pub fn unlock_time(day: u8) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(FIRST_UNLOCK + (day.max(1) as u64 - 1) * 24 * 60 * 60)
+}
+
+/// How long until `day` unlocks, or `None` if it already has.
+pub fn time_until_unlock(day: u8) -> Option<Duration> {
+    unlock_time(day).duration_since(SystemTime::now()).ok()
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes, seconds) =
+        (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    if days > 0 {
+        format!("{}d {:02}:{:02}:{:02}", days, hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+    }
+}
+
+/// The first day whose puzzle hasn't unlocked yet, if any.
+pub fn next_locked_day() -> Option<u8> {
+    DAYS.clone().find(|&day| time_until_unlock(day).is_some())
+}
+
+pub fn check_day(day: u8) -> Result<(), Error> {
+    match DAYS.contains(&day) {
+        true => Ok(()),
+        false => Err(Error::InvalidDay(day)),
+    }
+}
+
+fn ensure_unlocked(day: u8) -> Result<(), Error> {
+    check_day(day)?;
+    match time_until_unlock(day) {
+        Some(remaining) => Err(Error::Locked(day, remaining)),
+        None => Ok(()),
     }
+}
 
-    let client = reqwest::blocking::Client::builder()
+/// Reject bodies that can't be a puzzle input: empty responses and the HTML
+/// pages the site serves instead of the input when the session is invalid.
+fn validate_input(body: &[u8]) -> Result<(), Error> {
+    let text = String::from_utf8_lossy(body);
+    let text = text.trim_start();
+    if text.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+    let lower = text
+        .chars()
+        .take(64)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if lower.starts_with("<!doctype") || lower.starts_with("<html") {
+        return Err(Error::HtmlInput);
+    }
+    Ok(())
+}
+
+/// Reject empty pages.
+pub(crate) fn validate_page(body: &[u8]) -> Result<(), Error> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Err(Error::EmptyInput);
+    }
+    Ok(())
+}
+
+/// Reject anything that isn't a JSON object, typically the login page served
+/// when the session has expired.
+pub(crate) fn validate_json(body: &[u8]) -> Result<(), Error> {
+    match body.iter().find(|b| !b.is_ascii_whitespace()) {
+        None => Err(Error::EmptyInput),
+        Some(b'{') => Ok(()),
+        Some(_) => Err(Error::NotJson),
+    }
+}
+
+/// The User-Agent sent with every request, as the site's automation guidelines
+/// ask for a way to contact whoever runs the tool.
+pub fn user_agent(config: &Config) -> Result<String, Error> {
+    match config.contact.as_deref().map(str::trim) {
+        Some(contact) if !contact.is_empty() => Ok(format!(
+            "aoc2021-harness/{} ({})",
+            env!("CARGO_PKG_VERSION"),
+            contact
+        )),
+        _ => Err(Error::NoContact),
+    }
+}
+
+/// Whether `host` matches a `NO_PROXY` style entry: an exact host, a domain
+/// suffix (with or without a leading dot), or `*` for everything.
+fn bypasses_proxy(no_proxy: &[String], host: &str) -> bool {
+    no_proxy.iter().any(|entry| {
+        let entry = entry.trim_start_matches('.');
+        entry == "*"
+            || host.eq_ignore_ascii_case(entry)
+            || host
+                .to_ascii_lowercase()
+                .ends_with(&format!(".{}", entry.to_ascii_lowercase()))
+    })
+}
+
+/// Split a PEM bundle into its individual certificates.
+fn split_pem(bundle: &str) -> Vec<&str> {
+    const END: &str = "-----END CERTIFICATE-----";
+    bundle
+        .match_indices("-----BEGIN CERTIFICATE-----")
+        .filter_map(|(start, _)| {
+            bundle[start..]
+                .find(END)
+                .map(|len| &bundle[start..start + len + END.len()])
+        })
+        .collect()
+}
+
+fn load_certificates(path: &Path) -> Result<Vec<reqwest::Certificate>, Error> {
+    let bundle = std::fs::read_to_string(path)
+        .map_err(|err| Error::ReadCertificate(err, path.to_owned()))?;
+    let pems = split_pem(&bundle);
+    if pems.is_empty() {
+        return Err(Error::NoCertificate(path.to_owned()));
+    }
+    pems.into_iter()
+        .map(|pem| {
+            reqwest::Certificate::from_pem(pem.as_bytes())
+                .map_err(|err| Error::Certificate(err, path.to_owned()))
+        })
+        .collect()
+}
+
+/// Build the client every request goes through, with the configured
+/// User-Agent, proxy and root certificates.
+pub fn client(config: &Config) -> Result<reqwest::blocking::Client, Error> {
+    if config.is_offline() {
+        return Err(Error::Offline);
+    }
+    let mut builder = reqwest::blocking::Client::builder()
         .gzip(true)
         .timeout(std::time::Duration::from_secs(5))
-        .build()
-        .map_err(Error::ClientBuilder)?;
-
-    let mut response = client
-        .get(&input_url_for_day(day))
-        .header(
-            reqwest::header::COOKIE,
-            format!("session={}", config.session),
-        )
-        .send()
-        .map_err(Error::RequestingInput)?
-        .error_for_status()
-        .map_err(Error::ResponseStatus)?;
+        .user_agent(user_agent(config)?);
+
+    if let Some(proxy) = config.proxy() {
+        let proxy = if proxy.contains("://") {
+            proxy
+        } else {
+            format!("http://{}", proxy)
+        };
+        let proxy_url = reqwest::Url::parse(&proxy).map_err(|_| Error::Proxy(proxy))?;
+        let no_proxy = config.no_proxy();
+        builder = builder.proxy(reqwest::Proxy::custom(move |url| match url.host_str() {
+            Some(host) if bypasses_proxy(&no_proxy, host) => None,
+            _ => Some(proxy_url.clone()),
+        }));
+    }
+
+    for path in config.ca_certs() {
+        for certificate in load_certificates(&path)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    builder.build().map_err(Error::ClientBuilder)
+}
+
+/// Write `contents` next to `path` and rename it into place, so a reader never
+/// observes a partially written file.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_owned();
+    tmp_name.push(".part");
+    let tmp_path = path.with_file_name(tmp_name);
 
-    if let Some(parent) = input_path.parent() {
+    let result = std::fs::File::create(&tmp_path).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    result
+        .and_then(|_| std::fs::rename(&tmp_path, path))
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(&tmp_path);
+        })
+}
+
+/// Take an exclusive lock on a file next to `path`, so concurrent runs don't
+/// both fetch it. The lock is released when the returned file is dropped.
+fn lock_for(path: &Path) -> Result<std::fs::File, Error> {
+    if let Some(parent) = path.parent() {
         if !parent.exists() {
             std::fs::create_dir_all(parent)?;
         }
     }
+    let mut lock_name = path.file_name().unwrap_or_default().to_owned();
+    lock_name.push(".lock");
+    let lock_file = std::fs::File::create(path.with_file_name(lock_name))?;
+    lock_file.lock_exclusive()?;
+    Ok(lock_file)
+}
+
+/// GET `url` through the session's response cache. A cached copy younger than
+/// the resource's minimum refresh interval is returned without a request; an
+/// older one is revalidated with a conditional request. In offline mode any
+/// cached copy is returned, however old.
+pub fn fetch(
+    config: &Config,
+    url: &str,
+    resource: Resource,
+    validate: fn(&[u8]) -> Result<(), Error>,
+) -> Result<Vec<u8>, Error> {
+    fetch_from(
+        &Cache::for_session(&config.session),
+        config,
+        url,
+        resource,
+        validate,
+    )
+}
 
-    let mut file = std::fs::File::create(input_path)?;
-    response.copy_to(&mut file).map_err(Error::Downloading)?;
+fn fetch_from(
+    cache: &Cache,
+    config: &Config,
+    url: &str,
+    resource: Resource,
+    validate: fn(&[u8]) -> Result<(), Error>,
+) -> Result<Vec<u8>, Error> {
+    use reqwest::{header, StatusCode};
 
-    Ok(())
+    let cached = cache.load(url)?;
+    if let Some((meta, body)) = &cached {
+        if config.is_offline() || meta.age() < resource.min_refresh() {
+            return Ok(body.clone());
+        }
+    }
+    if config.is_offline() {
+        return Err(Error::NotCached(url.to_string(), cache.dir().clone()));
+    }
+
+    let mut request = client(config)?
+        .get(url)
+        .header(header::COOKIE, format!("session={}", config.session));
+    if let Some((meta, _)) = &cached {
+        if let Some(etag) = &meta.etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &meta.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = request
+        .send()
+        .map_err(|err| Error::Requesting(err, url.to_string()))?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        if let Some((mut meta, body)) = cached {
+            meta.touch();
+            cache.store(&meta, &body)?;
+            return Ok(body);
+        }
+    }
+    let response = response.error_for_status().map_err(Error::ResponseStatus)?;
+
+    let header_value = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value: &header::HeaderValue| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let meta = Meta::new(
+        url,
+        header_value(header::ETAG),
+        header_value(header::LAST_MODIFIED),
+    );
+    let body = response.bytes().map_err(Error::Downloading)?.to_vec();
+    validate(&body)?;
+    cache.store(&meta, &body)?;
+    Ok(body)
+}
+
+pub fn get_input(config: &Config, day: u8) -> Result<(), Error> {
+    let input_path = config.input_for(day);
+    if input_path.exists() {
+        return Ok(());
+    }
+    if config.is_offline() {
+        return Err(Error::InputNotCached(day, input_path));
+    }
+    ensure_unlocked(day)?;
+
+    let _lock = lock_for(&input_path)?;
+    // whoever got the lock first may have finished the download meanwhile
+    if input_path.exists() {
+        return Ok(());
+    }
+    let body = fetch(
+        config,
+        &input_url_for_day(day),
+        Resource::Input,
+        validate_input,
+    )?;
+    write_atomic(&input_path, &body).map_err(Into::into)
+}
+
+/// Fetch the puzzle page unless it is already on disk.
+pub fn get_puzzle(config: &Config, day: u8) -> Result<(), Error> {
+    let puzzle_path = config.puzzle_for(day);
+    if puzzle_path.exists() {
+        return Ok(());
+    }
+    ensure_unlocked(day)?;
+
+    let _lock = lock_for(&puzzle_path)?;
+    let body = fetch(config, &url_for_day(day), Resource::Puzzle, validate_page)?;
+    write_atomic(&puzzle_path, &body).map_err(Into::into)
+}
+
+/// Strip the tags from an HTML fragment and decode the few entities the site
+/// uses.
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&gt;", ">")
+        .replace("&lt;", "<")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Today's date in UTC as `YYYY-MM-DD`.
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (year, month, day) = civil_date(secs);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The calendar date of a unix timestamp, after Howard Hinnant's
+/// `civil_from_days`.
+fn civil_date(secs: u64) -> (u64, u64, u64) {
+    let z = secs / 86400 + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// A unified diff from `old` to `new`, labelled with `path`.
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    labelled_diff(&format!("a/{}", path), &format!("b/{}", path), old, new)
+}
+
+pub(crate) fn labelled_diff(old_label: &str, new_label: &str, old: &str, new: &str) -> String {
+    similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .header(old_label, new_label)
+        .to_string()
+}
+
+/// Both sides of a three-way comparison as unified diffs against their common
+/// `base`: what changed locally in `ours`, then what changed in `theirs`.
+pub fn three_way_diff(path: &str, base: &str, ours: &str, theirs: &str) -> String {
+    let base_label = format!("{} (base)", path);
+    let mut diff = labelled_diff(&base_label, &format!("{} (ours)", path), base, ours);
+    diff.push_str(&labelled_diff(
+        &base_label,
+        &format!("{} (theirs)", path),
+        base,
+        theirs,
+    ));
+    diff
+}
+
+/// Call `f` until it succeeds, up to `attempts` times, backing off a little
+/// longer after every failure so a just-unlocked server isn't hammered.
+pub fn with_retries<T>(attempts: u32, mut f: impl FnMut() -> Result<T, Error>) -> Result<T, Error> {
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Err(err) if attempt < attempts && err.is_transient() => {
+                let delay = Duration::from_secs(5 * attempt as u64);
+                eprintln!(
+                    "attempt {} failed: {}; retrying in {:?}",
+                    attempt, err, delay
+                );
+                std::thread::sleep(delay);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("building request client")]
     ClientBuilder(#[source] reqwest::Error),
-    #[error("requesting input file")]
-    RequestingInput(#[source] reqwest::Error),
+    #[error("requesting {1}")]
+    Requesting(#[source] reqwest::Error, String),
     #[error("response status unsuccessful")]
     ResponseStatus(#[source] reqwest::Error),
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error("downloading to local file")]
     Downloading(#[source] reqwest::Error),
+    #[error(transparent)]
+    Cache(#[from] crate::cache::Error),
+    #[error("downloaded input is empty")]
+    EmptyInput,
+    #[error("downloaded input is an HTML page; is the session cookie still valid?")]
+    HtmlInput,
+    #[error("expected a JSON response; is the session cookie still valid?")]
+    NotJson,
+    #[error("there is no puzzle for day {0}")]
+    InvalidDay(u8),
+    #[error("day {0} is still locked; it unlocks in {}", format_duration(*.1))]
+    Locked(u8, Duration),
+    #[error(
+        "no contact configured; the site asks automated tools to identify themselves, \
+         set one with `aoc2021 config set --contact <email or url>`"
+    )]
+    NoContact,
+    #[error("offline mode is enabled; refusing to make network requests")]
+    Offline,
+    #[error("input for day {0} not cached at {}", .1.display())]
+    InputNotCached(u8, PathBuf),
+    #[error("{0} not cached in {}", .1.display())]
+    NotCached(String, PathBuf),
+    #[error("invalid proxy url: {0}")]
+    Proxy(String),
+    #[error("reading CA certificate {}", .1.display())]
+    ReadCertificate(#[source] std::io::Error, PathBuf),
+    #[error("no PEM certificate found in {}", .0.display())]
+    NoCertificate(PathBuf),
+    #[error("invalid CA certificate in {}", .1.display())]
+    Certificate(#[source] reqwest::Error, PathBuf),
+}
+
+impl Error {
+    /// Whether trying again later might succeed.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::Requesting(..)
+                | Self::ResponseStatus(_)
+                | Self::Downloading(_)
+                | Self::EmptyInput
+                | Self::Locked(..)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate_input() {
+        assert!(validate_input(b"199\n200\n208\n").is_ok());
+        assert!(matches!(validate_input(b""), Err(Error::EmptyInput)));
+        assert!(matches!(validate_input(b" \n"), Err(Error::EmptyInput)));
+        assert!(matches!(
+            validate_input(b"<!DOCTYPE html>\n<html lang=\"en-us\">"),
+            Err(Error::HtmlInput)
+        ));
+        assert!(matches!(
+            validate_input(b"\n<HTML><body>log in</body></HTML>"),
+            Err(Error::HtmlInput)
+        ));
+    }
+
+    #[test]
+    fn test_user_agent() {
+        let mut config = Config::default();
+        assert!(matches!(user_agent(&config), Err(Error::NoContact)));
+        config.contact = Some("  ".to_string());
+        assert!(matches!(user_agent(&config), Err(Error::NoContact)));
+        config.contact = Some("me@example.com".to_string());
+        assert_eq!(
+            user_agent(&config).unwrap(),
+            format!(
+                "aoc2021-harness/{} (me@example.com)",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn test_bypasses_proxy() {
+        let no_proxy = vec!["localhost".to_string(), ".corp.example".to_string()];
+        assert!(bypasses_proxy(&no_proxy, "localhost"));
+        assert!(bypasses_proxy(&no_proxy, "git.corp.example"));
+        assert!(bypasses_proxy(&no_proxy, "CORP.example"));
+        assert!(!bypasses_proxy(&no_proxy, "adventofcode.com"));
+        assert!(bypasses_proxy(&["*".to_string()], "adventofcode.com"));
+    }
+
+    #[test]
+    fn test_split_pem() {
+        let bundle = "# root\n-----BEGIN CERTIFICATE-----\nAAA\n-----END CERTIFICATE-----\n\
+                      -----BEGIN CERTIFICATE-----\nBBB\n-----END CERTIFICATE-----\n";
+        assert_eq!(
+            split_pem(bundle),
+            [
+                "-----BEGIN CERTIFICATE-----\nAAA\n-----END CERTIFICATE-----",
+                "-----BEGIN CERTIFICATE-----\nBBB\n-----END CERTIFICATE-----"
+            ]
+        );
+        assert!(split_pem("not a certificate").is_empty());
+    }
+
+    #[test]
+    fn test_unlock_time() {
+        let secs = |day| {
+            unlock_time(day)
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        };
+        assert_eq!(secs(1), 1_638_334_800);
+        assert_eq!(secs(25), 1_638_334_800 + 24 * 86400);
+        assert_eq!(time_until_unlock(1), None);
+        assert_eq!(next_locked_day(), None);
+    }
+
+    #[test]
+    fn test_check_day() {
+        assert!(check_day(1).is_ok());
+        assert!(check_day(25).is_ok());
+        assert!(matches!(check_day(0), Err(Error::InvalidDay(0))));
+        assert!(matches!(check_day(26), Err(Error::InvalidDay(26))));
+    }
+
+    #[test]
+    fn test_civil_date() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(FIRST_UNLOCK), (2021, 12, 1));
+        assert_eq!(civil_date(951_782_400), (2000, 2, 29));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(59)), "00:00:59");
+        assert_eq!(
+            format_duration(Duration::from_secs(3 * 3600 + 61)),
+            "03:01:01"
+        );
+        assert_eq!(
+            format_duration(Duration::from_secs(2 * 86400 + 5)),
+            "2d 00:00:05"
+        );
+    }
+
+    #[test]
+    fn test_fetch_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().to_owned());
+        let config = Config {
+            offline: true,
+            ..Config::default()
+        };
+        let url = "https://adventofcode.com/2021/leaderboard/self";
+        assert!(matches!(
+            fetch_from(&cache, &config, url, Resource::Stats, validate_page),
+            Err(Error::NotCached(..))
+        ));
+
+        // however old, a cached copy is used without a request
+        let mut meta = Meta::new(url, None, None);
+        meta.fetched_at = 0;
+        cache.store(&meta, b"<html>stats</html>").unwrap();
+        assert_eq!(
+            fetch_from(&cache, &config, url, Resource::Stats, validate_page).unwrap(),
+            b"<html>stats</html>"
+        );
+    }
+
+    #[test]
+    fn test_get_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            offline: true,
+            input_files: Some(dir.path().to_owned()),
+            ..Config::default()
+        };
+        assert!(matches!(
+            get_input(&config, 1),
+            Err(Error::InputNotCached(1, _))
+        ));
+
+        std::fs::write(config.input_for(1), "199\n").unwrap();
+        std::fs::write(config.puzzle_for(1), "<h2>--- Day 1: Sonar Sweep ---</h2>").unwrap();
+        assert!(get_input(&config, 1).is_ok());
+        assert!(get_puzzle(&config, 1).is_ok());
+    }
 }
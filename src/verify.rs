@@ -0,0 +1,267 @@
+use std::{path::Path, process::Command, str::FromStr};
+
+use thiserror::Error;
+use toml_edit::{Document, Item, Table};
+
+use crate::{answer::Answer, day::workspace_members};
+
+/// Expected answers by day and part, as stored in `answers.toml`:
+///
+/// ```toml
+/// [day01]
+/// part1 = "1675"
+/// part2 = "1722"
+/// ```
+#[derive(Debug, Clone)]
+pub struct Answers {
+    document: Document,
+}
+
+impl Answers {
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        Ok(Self {
+            document: Document::from_str(text)?,
+        })
+    }
+
+    /// The answers at `path`, or none if the file doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        if !path.exists() {
+            return Self::parse("");
+        }
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = self.document.to_string();
+        crate::utils::write_atomic(path, text.trim_start().as_bytes())?;
+        Ok(())
+    }
+
+    /// The expected answer for `part` of `day`; numbers may be written without
+    /// quotes.
+    pub fn get(&self, day: u8, part: u8) -> Option<String> {
+        let item = self
+            .document
+            .as_table()
+            .get(&format!("day{:02}", day))?
+            .as_table_like()?
+            .get(&format!("part{}", part))?;
+        item.as_str()
+            .map(str::to_string)
+            .or_else(|| item.as_integer().map(|number| number.to_string()))
+    }
+
+    pub fn set(&mut self, day: u8, part: u8, answer: &str) -> Result<(), Error> {
+        let table = self
+            .document
+            .as_table_mut()
+            .entry(&format!("day{:02}", day))
+            .or_insert(Item::Table(Table::new()))
+            .as_table_mut()
+            .ok_or(Error::Malformed(day))?;
+        table.insert(&format!("part{}", part), toml_edit::value(answer));
+        Ok(())
+    }
+}
+
+/// How a part's result compares to the expected answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    Match,
+    Mismatch {
+        expected: String,
+        actual: String,
+    },
+    /// Nothing expected yet; `recorded` if the result was written down
+    Unknown {
+        actual: String,
+        recorded: bool,
+    },
+    /// The solution didn't run to completion
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartCheck {
+    pub day: u8,
+    pub part: u8,
+    pub check: Check,
+}
+
+fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // skip to the end of the escape sequence, a letter
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// The error a day binary died with: the first line after `Error:` in a
+/// color-eyre report, or else the last line of its output.
+fn error_message(stderr: &str) -> String {
+    let mut lines = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    let message = match lines.by_ref().position(|line| line.starts_with("Error:")) {
+        Some(_) => lines.next(),
+        None => stderr.lines().map(str::trim).rfind(|line| !line.is_empty()),
+    };
+    let message = message.unwrap_or("no output");
+    message.strip_prefix("0: ").unwrap_or(message).to_string()
+}
+
+/// Run one part of a day's binary and return what it printed.
+fn run_part(root: &Path, day: u8, part: u8) -> Result<String, String> {
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let mut command = Command::new(cargo);
+    command
+        .current_dir(root)
+        .args(["run", "--release", "--quiet", "--package"])
+        .arg(format!("day{:02}", day))
+        .arg("--");
+    if part == 2 {
+        command.args(["--no-part1", "--part2"]);
+    }
+    let output = command.output().map_err(|err| err.to_string())?;
+    if !output.status.success() {
+        let stderr = strip_ansi(&String::from_utf8_lossy(&output.stderr));
+        return Err(format!("{}: {}", output.status, error_message(&stderr)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Compare what a part printed with the expected answer. Art is compared by
+/// the letters it shows, falling back to the drawing itself if it can't be
+/// read.
+fn check(expected: Option<&str>, output: &str) -> Check {
+    let actual = Answer::from_output(output).submission();
+    match expected {
+        Some(expected) if expected.trim() == actual => Check::Match,
+        Some(expected) => Check::Mismatch {
+            expected: expected.trim().to_string(),
+            actual,
+        },
+        None => Check::Unknown {
+            actual,
+            recorded: false,
+        },
+    }
+}
+
+/// Run both parts of every day in `days` that is a workspace member and check
+/// them against the answers in `answers_path`. With `record`, results for
+/// parts without an expected answer are written to the file; existing answers
+/// are never changed.
+pub fn verify(
+    root: &Path,
+    answers_path: &Path,
+    days: &[u8],
+    record: bool,
+) -> Result<Vec<PartCheck>, Error> {
+    let members = workspace_members(root)?;
+    let mut answers = Answers::load(answers_path)?;
+    let mut changed = false;
+    let mut checks = Vec::new();
+
+    for &day in days {
+        if !members.contains(&format!("day{:02}", day)) {
+            continue;
+        }
+        for part in 1..=2 {
+            let check = match run_part(root, day, part) {
+                Ok(output) => check(answers.get(day, part).as_deref(), &output),
+                Err(reason) => Check::Failed(reason),
+            };
+            let check = match check {
+                Check::Unknown { actual, .. } if record => {
+                    answers.set(day, part, &actual)?;
+                    changed = true;
+                    Check::Unknown {
+                        actual,
+                        recorded: true,
+                    }
+                }
+                check => check,
+            };
+            checks.push(PartCheck { day, part, check });
+        }
+    }
+
+    if changed {
+        answers.save(answers_path)?;
+    }
+    Ok(checks)
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Workspace(#[from] crate::day::Error),
+    #[error("could not parse the answers file")]
+    Parse(#[from] toml_edit::TomlError),
+    #[error("day{0:02} in the answers file is not a table")]
+    Malformed(u8),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_answers() {
+        let mut answers =
+            Answers::parse("# mine\n[day01]\npart1 = 1675\npart2 = \"1722\"\n").unwrap();
+        assert_eq!(answers.get(1, 1).as_deref(), Some("1675"));
+        assert_eq!(answers.get(1, 2).as_deref(), Some("1722"));
+        assert_eq!(answers.get(2, 1), None);
+
+        answers.set(2, 1, "HBCERHER").unwrap();
+        assert_eq!(answers.get(2, 1).as_deref(), Some("HBCERHER"));
+        assert!(answers.document.to_string().starts_with("# mine\n"));
+    }
+
+    #[test]
+    fn test_error_message() {
+        let report = "Error: \n   \u{1b}[91m0: \u{1b}[91minput not cached\u{1b}[0m\n\nLocation:\n   src/main.rs:42\n\nRun with RUST_BACKTRACE=full to include source snippets.\n";
+        assert_eq!(error_message(&strip_ansi(report)), "input not cached");
+        assert_eq!(error_message("thread 'main' panicked\nboom\n"), "boom");
+    }
+
+    #[test]
+    fn test_check() {
+        assert_eq!(check(Some("1675"), "1675\n"), Check::Match);
+        assert_eq!(
+            check(Some("1675"), "1676\n"),
+            Check::Mismatch {
+                expected: "1675".to_string(),
+                actual: "1676".to_string()
+            }
+        );
+        let art = ".##..###.\n#..#.#..#\n#..#.###.\n####.#..#\n#..#.#..#\n#..#.###.\n";
+        assert_eq!(check(Some("AB"), art), Check::Match);
+        assert!(matches!(
+            check(None, "42\n"),
+            Check::Unknown {
+                recorded: false,
+                ..
+            }
+        ));
+    }
+}